@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xdg_desktop_parser::{trashinfo::TrashInfo, KeyFile, XdgDesktopFile};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(file) = XdgDesktopFile::from_bytes(data) {
+        let _ = file.validate();
+        let _ = file.to_string().parse::<XdgDesktopFile>();
+    }
+    let _ = XdgDesktopFile::parser()
+        .strict(true)
+        .lossless(true)
+        .parse_bytes(data)
+        .map(|f| f.to_string());
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = s.parse::<KeyFile>();
+        let _ = s.parse::<TrashInfo>();
+    }
+});
@@ -0,0 +1,298 @@
+//! Checks, formats and queries desktop entries from the command line.
+//!
+//! ```text
+//! xdg-desktop-tool lint FILE...
+//! xdg-desktop-tool fmt [--check] [FILE...]
+//! xdg-desktop-tool get FILE SECTION KEY [--locale LOCALE]
+//! ```
+//!
+//! `lint` exits with 1 if any file has errors, which include invalid UTF-8; warnings alone
+//! don't fail it. `fmt` rewrites the files in canonical form, keeping their comments, or with
+//! `--check` exits with 1 if any isn't; without files, it formats standard input to standard
+//! output; files that aren't valid UTF-8 are never rewritten. `get` prints a value, a list
+//! one item per line, and exits with 1 if the key isn't set. In `SECTION`, `-` may stand for
+//! a space, so `Desktop-Entry` is `[Desktop Entry]`. Usage errors, unreadable files and files
+//! that `fmt` refuses exit with 2.
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+use xdg_desktop_parser::{XdgDesktopFile, XdgDesktopSection, XdgDesktopValue, XdgParseWarning};
+
+const USAGE: &str = "usage: xdg-desktop-tool lint FILE...
+       xdg-desktop-tool fmt [--check] [FILE...]
+       xdg-desktop-tool get FILE SECTION KEY [--locale LOCALE]";
+
+/// Why a command failed, which determines the exit code.
+#[derive(Debug)]
+enum Failure {
+    /// The command ran, but found errors or nothing to print.
+    Check,
+    /// The command couldn't run.
+    Fatal(String),
+}
+
+type Outcome = Result<(), Failure>;
+
+fn fatal(path: &str, e: impl ToString) -> Failure {
+    Failure::Fatal(format!("{}: {}", path, e.to_string()))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, Failure> {
+    fs::read(path).map_err(|e| fatal(path, e))
+}
+
+/// Parses `contents` losslessly, replacing invalid UTF-8, which is reported as a warning.
+fn parse(path: &str, contents: &[u8]) -> Result<XdgDesktopFile, Failure> {
+    XdgDesktopFile::parser()
+        .lossless(true)
+        .parse_bytes(contents)
+        .map_err(|e| fatal(path, e))
+}
+
+fn is_invalid_utf8(w: &XdgParseWarning) -> bool {
+    matches!(w, XdgParseWarning::InvalidUtf8 { .. })
+}
+
+/// Prints the errors and warnings of each file, as `XdgDesktopFile::validate` and
+/// `XdgDesktopFile::report` find them.
+fn lint(paths: &[String], out: &mut impl Write) -> Outcome {
+    if paths.is_empty() {
+        return Err(Failure::Fatal(USAGE.to_string()));
+    }
+    let mut failed = false;
+    for path in paths {
+        let file = match XdgDesktopFile::from_bytes(&read(path)?) {
+            Ok(file) => file,
+            Err(e) => {
+                writeln!(out, "{}: error: {}", path, e).ok();
+                failed = true;
+                continue;
+            }
+        };
+        let report = file.report();
+        let (invalid_utf8, warnings): (Vec<_>, Vec<_>) =
+            report.iter().partition(|w| is_invalid_utf8(w));
+        let errors: Vec<String> = invalid_utf8
+            .iter()
+            .map(ToString::to_string)
+            .chain(
+                file.errors()
+                    .map(|(section, key, e)| format!("[{}] {}: {}", section, key, e)),
+            )
+            .chain(file.validate().iter().map(ToString::to_string))
+            .collect();
+        failed |= !errors.is_empty();
+        for e in errors {
+            writeln!(out, "{}: error: {}", path, e).ok();
+        }
+        for w in warnings {
+            writeln!(out, "{}: warning: {}", path, w).ok();
+        }
+    }
+    if failed {
+        Err(Failure::Check)
+    } else {
+        Ok(())
+    }
+}
+
+/// Formats `contents`, refusing to if it isn't valid UTF-8, as writing it back would replace
+/// the invalid bytes.
+fn format(path: &str, contents: &[u8]) -> Result<String, Failure> {
+    let mut file = parse(path, contents)?;
+    if let Some(w) = file.warnings().iter().find(|w| is_invalid_utf8(w)) {
+        return Err(fatal(path, format_args!("{}; not formatting", w)));
+    }
+    file.normalize();
+    Ok(file.to_string())
+}
+
+/// Rewrites each file in canonical form; see `XdgDesktopFile::normalize`.
+fn fmt(args: &[String], out: &mut impl Write) -> Outcome {
+    let check = args.iter().any(|a| a == "--check");
+    let paths: Vec<&String> = args.iter().filter(|a| *a != "--check").collect();
+    if paths.is_empty() {
+        let mut contents = Vec::new();
+        io::stdin()
+            .read_to_end(&mut contents)
+            .map_err(|e| fatal("<stdin>", e))?;
+        let formatted = format("<stdin>", &contents)?;
+        if check {
+            return if formatted.as_bytes() == contents {
+                Ok(())
+            } else {
+                Err(Failure::Check)
+            };
+        }
+        return out
+            .write_all(formatted.as_bytes())
+            .map_err(|e| fatal("<stdout>", e));
+    }
+    let mut unformatted = false;
+    for path in paths {
+        let contents = read(path)?;
+        let formatted = format(path, &contents)?;
+        if formatted.as_bytes() == contents {
+            continue;
+        }
+        if check {
+            writeln!(out, "{}", path).ok();
+            unformatted = true;
+        } else {
+            fs::write(path, formatted).map_err(|e| fatal(path, e))?;
+        }
+    }
+    if unformatted {
+        Err(Failure::Check)
+    } else {
+        Ok(())
+    }
+}
+
+/// Finds `name`, or `name` with its hyphens read as spaces.
+fn find_section<'a>(file: &'a XdgDesktopFile, name: &str) -> Option<&'a XdgDesktopSection> {
+    file.section(name)
+        .or_else(|| file.section(&name.replace('-', " ")))
+}
+
+fn print_value(value: &XdgDesktopValue, out: &mut impl Write) -> io::Result<()> {
+    match value {
+        XdgDesktopValue::String(s)
+        | XdgDesktopValue::LocaleString(s)
+        | XdgDesktopValue::IconString(s) => writeln!(out, "{}", s),
+        XdgDesktopValue::List(l) => l.iter().try_for_each(|v| print_value(v, out)),
+        other => writeln!(out, "{}", other),
+    }
+}
+
+/// Prints the value of a key, translated for `--locale` if given.
+fn get(args: &[String], out: &mut impl Write) -> Outcome {
+    let mut locale = None;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--locale" {
+            let l = args.next();
+            locale = Some(l.ok_or_else(|| Failure::Fatal(USAGE.to_string()))?.as_str());
+        } else if let Some(l) = arg.strip_prefix("--locale=") {
+            locale = Some(l);
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+    let (path, section, key) = match positional[..] {
+        [path, section, key] => (path, section, key),
+        _ => return Err(Failure::Fatal(USAGE.to_string())),
+    };
+    let file = parse(path, &read(path)?)?;
+    let section = find_section(&file, section).ok_or(Failure::Check)?;
+    let value = match locale {
+        Some(locale) => section.get_localized(key, locale),
+        None => section.get(key),
+    };
+    print_value(value.ok_or(Failure::Check)?, out).map_err(|e| fatal("<stdout>", e))
+}
+
+fn run(args: &[String], out: &mut impl Write) -> Outcome {
+    match args.split_first() {
+        Some((command, rest)) => match command.as_str() {
+            "lint" => lint(rest, out),
+            "fmt" => fmt(rest, out),
+            "get" => get(rest, out),
+            "-h" | "--help" => writeln!(out, "{}", USAGE).map_err(|e| fatal("<stdout>", e)),
+            _ => Err(Failure::Fatal(USAGE.to_string())),
+        },
+        None => Err(Failure::Fatal(USAGE.to_string())),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args, &mut io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Check) => ExitCode::from(1),
+        Err(Failure::Fatal(message)) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn run_with(args: &[&str]) -> (Outcome, String) {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut out = Vec::new();
+        let outcome = run(&args, &mut out);
+        (outcome, String::from_utf8(out).unwrap())
+    }
+
+    fn temp_file(dir: &Path, name: &str, contents: impl AsRef<[u8]>) -> String {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = temp_file(
+            dir.path(),
+            "entry.desktop",
+            "# Copyright\n\n[Desktop Entry]\nType=Application\nExec=foo\n\
+             # Translators: keep it short\nName[de]=Dateien\nKeywords=a;b\\;c;\nName=Files\n",
+        );
+        let (outcome, out) = run_with(&["get", &entry, "Desktop-Entry", "Name", "--locale", "de"]);
+        assert!(outcome.is_ok());
+        assert_eq!(out, "Dateien\n");
+        let (_, out) = run_with(&["get", &entry, "Desktop Entry", "Keywords"]);
+        assert_eq!(out, "a\nb;c\n");
+        let (outcome, _) = run_with(&["get", &entry, "Desktop Entry", "Icon"]);
+        assert!(matches!(outcome, Err(Failure::Check)));
+
+        let (outcome, out) = run_with(&["lint", &entry]);
+        assert!(outcome.is_ok(), "{}", out);
+        let broken = temp_file(
+            dir.path(),
+            "broken.desktop",
+            "[Desktop Entry]\nType=Application\n",
+        );
+        let (outcome, out) = run_with(&["lint", &entry, &broken]);
+        assert!(matches!(outcome, Err(Failure::Check)));
+        assert!(out.lines().all(|l| l.starts_with(&broken)), "{}", out);
+        assert!(out.contains(": error: "));
+
+        let (outcome, out) = run_with(&["fmt", "--check", &entry]);
+        assert!(matches!(outcome, Err(Failure::Check)));
+        assert_eq!(out, format!("{}\n", entry));
+        assert!(run_with(&["fmt", &entry]).0.is_ok());
+        assert!(fs::read_to_string(&entry).unwrap().starts_with(
+            "# Copyright\n\n[Desktop Entry]\nType=Application\nName=Files\n\
+             # Translators: keep it short\nName[de]=Dateien\n"
+        ));
+        assert!(run_with(&["fmt", "--check", &entry]).0.is_ok());
+
+        let latin1 = b"[Desktop Entry]\nType=Application\nName=Caf\xe9\nExec=cafe\n";
+        let latin1_path = temp_file(dir.path(), "latin1.desktop", latin1);
+        let (outcome, out) = run_with(&["lint", &latin1_path]);
+        assert!(matches!(outcome, Err(Failure::Check)));
+        assert!(out.contains("error: Line 3: invalid UTF-8"), "{}", out);
+        assert!(matches!(
+            run_with(&["fmt", &latin1_path]).0,
+            Err(Failure::Fatal(_))
+        ));
+        assert_eq!(fs::read(&latin1_path).unwrap(), latin1);
+
+        assert!(matches!(
+            run_with(&["get", &entry]).0,
+            Err(Failure::Fatal(_))
+        ));
+        assert!(matches!(run_with(&[]).0, Err(Failure::Fatal(_))));
+    }
+}
@@ -0,0 +1,241 @@
+//! A shared, queryable set of desktop entries, as a backend for launchers and shell extensions.
+//!
+//! An [`AppDatabase`] can be shared between threads and queried by ID, category, MIME type or
+//! search term while it is updated. Entries are handed out behind an [`Arc`], so that callers
+//! can hold on to them without keeping the database locked.
+
+use crate::{discovery::Discovery, search::SearchIndex, xdg_category::Category, XdgDesktopFile};
+use indexmap::IndexMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// An entry of an [`AppDatabase`], with its desktop file ID.
+pub type AppEntry = (String, Arc<XdgDesktopFile>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: IndexMap<String, Arc<XdgDesktopFile>>,
+    index: SearchIndex,
+}
+
+/// A thread-safe collection of desktop entries, keyed by desktop file ID.
+///
+/// ```
+/// # use xdg_desktop_parser::{database::AppDatabase, XdgDesktopFile};
+/// let db = AppDatabase::new();
+/// let entry: XdgDesktopFile = "[Desktop Entry]\nName=Files\nCategories=System;\n".parse().unwrap();
+/// db.insert("org.gnome.Nautilus.desktop", entry);
+/// assert_eq!(db.search("fil")[0].0, "org.gnome.Nautilus.desktop");
+/// assert_eq!(db.with_category("System").len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct AppDatabase {
+    inner: RwLock<Inner>,
+}
+
+impl AppDatabase {
+    /// Creates an empty database.
+    pub fn new() -> AppDatabase {
+        AppDatabase::default()
+    }
+
+    /// Loads every entry found by `discovery`. Files that can't be read or parsed are left
+    /// out.
+    pub fn load(discovery: &Discovery) -> AppDatabase {
+        AppDatabase::from_entries(
+            discovery
+                .load()
+                .into_iter()
+                .filter_map(|(id, file)| Some((id, file.ok()?))),
+        )
+    }
+
+    pub fn from_entries<S: Into<String>>(
+        entries: impl IntoIterator<Item = (S, XdgDesktopFile)>,
+    ) -> AppDatabase {
+        let db = AppDatabase::new();
+        {
+            let mut inner = db.write();
+            for (id, file) in entries {
+                inner.insert(id.into(), file);
+            }
+        }
+        db
+    }
+
+    // Poisoning is ignored: at worst, an update that panicked leaves an entry out of the
+    // search index.
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().entries.is_empty()
+    }
+
+    /// The ID of every entry, in the order they were added.
+    pub fn ids(&self) -> Vec<String> {
+        self.read().entries.keys().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<XdgDesktopFile>> {
+        self.read().entries.get(id).cloned()
+    }
+
+    /// Every entry, in the order they were added.
+    pub fn entries(&self) -> Vec<AppEntry> {
+        self.matching(|_| true)
+    }
+
+    /// The entries listing `category` in their `Categories`.
+    pub fn with_category(&self, category: impl Into<Category>) -> Vec<AppEntry> {
+        let category = category.into();
+        self.matching(|file| file.categories().contains(&category))
+    }
+
+    /// The entries that handle `mime`; see [`XdgDesktopFile::handles_mime`].
+    pub fn for_mime(&self, mime: &str) -> Vec<AppEntry> {
+        self.matching(|file| file.handles_mime(mime))
+    }
+
+    /// The entries matching `query`, best matches first; see [`SearchIndex::search`].
+    pub fn search(&self, query: &str) -> Vec<AppEntry> {
+        let inner = self.read();
+        inner
+            .index
+            .search(query)
+            .into_iter()
+            .filter_map(|hit| {
+                let file = inner.entries.get(hit.id)?;
+                Some((hit.id.to_string(), Arc::clone(file)))
+            })
+            .collect()
+    }
+
+    /// The entry a window with the class or app ID `wm_class` most likely belongs to, as
+    /// taskbars need to show its icon and name. Entries are ranked by
+    /// [`WmClassMatch`](crate::WmClassMatch), then kept in the order they were added.
+    pub fn find_by_wm_class(&self, wm_class: &str) -> Option<AppEntry> {
+        let inner = self.read();
+        let (_, id, file) = inner
+            .entries
+            .iter()
+            .filter_map(|(id, file)| Some((file.wm_class_match(Some(id), wm_class)?, id, file)))
+            .min_by_key(|(rank, _, _)| *rank)?;
+        Some((id.clone(), Arc::clone(file)))
+    }
+
+    fn matching(&self, f: impl Fn(&XdgDesktopFile) -> bool) -> Vec<AppEntry> {
+        self.read()
+            .entries
+            .iter()
+            .filter(|(_, file)| f(file))
+            .map(|(id, file)| (id.clone(), Arc::clone(file)))
+            .collect()
+    }
+
+    /// Adds the entry `file` under `id`, replacing any entry already there, which is
+    /// returned. A replaced entry counts as newly added: it moves to the end of the order.
+    pub fn insert(&self, id: &str, file: XdgDesktopFile) -> Option<Arc<XdgDesktopFile>> {
+        self.write().insert(id.to_string(), file)
+    }
+
+    pub fn remove(&self, id: &str) -> Option<Arc<XdgDesktopFile>> {
+        self.write().remove(id)
+    }
+
+    /// Applies the changes reported by a [`DesktopDatabaseWatcher`]. An entry that fails to
+    /// parse after a change is removed.
+    ///
+    /// [`DesktopDatabaseWatcher`]: crate::watch::DesktopDatabaseWatcher
+    #[cfg(feature = "watch")]
+    pub fn apply(&self, events: impl IntoIterator<Item = crate::watch::WatchEvent>) {
+        use crate::watch::WatchEvent;
+        let mut inner = self.write();
+        for event in events {
+            match event {
+                WatchEvent::Added { id, entry, .. } | WatchEvent::Changed { id, entry, .. } => {
+                    match entry {
+                        Ok(file) => inner.insert(id, file),
+                        Err(_) => inner.remove(&id),
+                    };
+                }
+                WatchEvent::Removed { id, .. } => {
+                    inner.remove(&id);
+                }
+            }
+        }
+    }
+}
+
+impl Inner {
+    // The search index appends, so the entry is moved to the end of `entries` too; that way
+    // both agree on the order entries were added in.
+    fn insert(&mut self, id: String, file: XdgDesktopFile) -> Option<Arc<XdgDesktopFile>> {
+        self.index.remove(&id);
+        self.index.insert(&id, &file);
+        let previous = self.entries.shift_remove(&id);
+        self.entries.insert(id, Arc::new(file));
+        previous
+    }
+
+    fn remove(&mut self, id: &str) -> Option<Arc<XdgDesktopFile>> {
+        self.index.remove(id);
+        self.entries.shift_remove(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{str::FromStr, thread};
+
+    #[test]
+    fn test_app_database() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AppDatabase>();
+
+        let entry = |s: &str| XdgDesktopFile::from_str(&format!("[Desktop Entry]\n{}", s)).unwrap();
+        let db = AppDatabase::from_entries(vec![
+            (
+                "viewer.desktop",
+                entry("Name=Image Viewer\nCategories=Graphics;Viewer;\nMimeType=image/png;\n"),
+            ),
+            (
+                "editor.desktop",
+                entry("Name=Text Editor\nCategories=Utility;\nMimeType=text/plain;\n"),
+            ),
+        ]);
+        assert_eq!(db.len(), 2);
+        let ids = |entries: Vec<AppEntry>| entries.into_iter().map(|e| e.0).collect::<Vec<_>>();
+        assert_eq!(ids(db.with_category("Graphics")), ["viewer.desktop"]);
+        assert_eq!(ids(db.for_mime("image/*")), ["viewer.desktop"]);
+        assert_eq!(ids(db.search("edit")), ["editor.desktop"]);
+        let by_class = |c| db.find_by_wm_class(c).map(|e| e.0);
+        assert_eq!(by_class("Editor"), Some("editor.desktop".to_string()));
+        assert_eq!(by_class("Image Viewer"), Some("viewer.desktop".to_string()));
+        assert_eq!(by_class("gimp"), None);
+
+        let held = db.get("editor.desktop").unwrap();
+        thread::scope(|s| {
+            s.spawn(|| db.insert("editor.desktop", entry("Name=Notepad\n")));
+        });
+        assert_eq!(held.get_str("Desktop Entry", "Name"), Some("Text Editor"));
+        assert!(db.search("edit").is_empty());
+        assert_eq!(ids(db.search("note")), ["editor.desktop"]);
+        db.insert("viewer.desktop", entry("Name=Notes Viewer\n"));
+        assert_eq!(db.ids(), ["editor.desktop", "viewer.desktop"]);
+        assert_eq!(ids(db.entries()), db.ids());
+        assert_eq!(ids(db.search("note")), db.ids());
+        assert!(db.remove("viewer.desktop").is_some());
+        assert!(db.search("image").is_empty());
+        assert_eq!(db.ids(), ["editor.desktop"]);
+    }
+}
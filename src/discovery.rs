@@ -0,0 +1,336 @@
+//! Enumeration of the desktop entries installed on the system.
+//!
+//! Entries are found in the `applications` subdirectory of each XDG data directory. Each is
+//! identified by its desktop file ID: its path relative to that directory, with `/` replaced
+//! by `-` (so `applications/kde/konsole.desktop` has the ID `kde-konsole.desktop`). When
+//! several directories provide the same ID, the one that comes first in the search path
+//! shadows the others.
+
+use crate::{xdg_base_dirs, XdgDesktopFile};
+use indexmap::IndexMap;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+#[cfg(not(feature = "rayon"))]
+use std::{num::NonZeroUsize, panic, thread};
+
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    dirs: Vec<PathBuf>,
+}
+
+/// A file hidden by another that provides the same desktop file ID from a directory earlier in
+/// the search path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowedFile {
+    pub id: String,
+    pub path: PathBuf,
+    /// The file that provides the entry instead.
+    pub shadowed_by: PathBuf,
+}
+
+impl Default for Discovery {
+    fn default() -> Discovery {
+        Discovery::new()
+    }
+}
+
+/// Computes the desktop file ID of `path`, a file somewhere below the applications directory
+/// `dir`. Returns `None` if `path` isn't below `dir` or isn't valid UTF-8.
+///
+/// ```
+/// # use xdg_desktop_parser::discovery::desktop_file_id;
+/// let id = desktop_file_id(
+///     "/usr/share/applications",
+///     "/usr/share/applications/kde/konsole.desktop",
+/// );
+/// assert_eq!(id.as_deref(), Some("kde-konsole.desktop"));
+/// ```
+pub fn desktop_file_id(dir: impl AsRef<Path>, path: impl AsRef<Path>) -> Option<String> {
+    let relative = path.as_ref().strip_prefix(dir).ok()?;
+    let parts: Option<Vec<&str>> = relative.iter().map(|c| c.to_str()).collect();
+    Some(parts?.join("-")).filter(|id| !id.is_empty())
+}
+
+/// Whether `name` can be joined to a directory without leaving it.
+fn is_file_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..")
+}
+
+/// Finds the file below `dir` with the desktop file ID `id`. Since both `/` and `-` become
+/// `-`, each `-` may stand for either, and each interpretation is tried: `a-b.desktop` may be
+/// `a-b.desktop` or `a/b.desktop`. Pieces that would name `dir` itself or its parent are
+/// never tried.
+fn resolve_id(dir: &Path, id: &str) -> Option<PathBuf> {
+    let path = dir.join(id);
+    if is_file_name(id) && path.is_file() {
+        return Some(path);
+    }
+    id.match_indices('-').find_map(|(i, _)| {
+        let subdir = dir.join(&id[..i]);
+        if is_file_name(&id[..i]) && subdir.is_dir() {
+            resolve_id(&subdir, &id[i + 1..])
+        } else {
+            None
+        }
+    })
+}
+
+/// Collects the `.desktop` files below `dir`, recursing into subdirectories. Unreadable
+/// directories are skipped, as are symbolic links to `dir` or any directory above it, in
+/// `ancestors`, which would otherwise be followed forever.
+fn scan(root: &Path, dir: &Path, ancestors: &mut Vec<PathBuf>, out: &mut Vec<(String, PathBuf)>) {
+    let canonical = match fs::canonicalize(dir) {
+        Ok(c) if !ancestors.contains(&c) => c,
+        _ => return,
+    };
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+        Err(_) => return,
+    };
+    // Directory iteration order is unspecified; sort for reproducible results.
+    paths.sort();
+    ancestors.push(canonical);
+    for path in paths {
+        if path.is_dir() {
+            scan(root, &path, ancestors, out);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            if let Some(id) = desktop_file_id(root, &path) {
+                out.push((id, path));
+            }
+        }
+    }
+    ancestors.pop();
+}
+
+/// Reads and parses `paths` concurrently, keeping their order.
+#[cfg(feature = "rayon")]
+pub(crate) fn parse_all(
+    paths: IndexMap<String, PathBuf>,
+) -> IndexMap<String, crate::Result<XdgDesktopFile>> {
+    use rayon::prelude::*;
+
+    let paths: Vec<(String, PathBuf)> = paths.into_iter().collect();
+    let parsed: Vec<_> = paths
+        .into_par_iter()
+        .map(|(id, path)| {
+            let file = XdgDesktopFile::from_path(&path);
+            (id, file)
+        })
+        .collect();
+    parsed.into_iter().collect()
+}
+
+/// Reads and parses `paths` concurrently, keeping their order.
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn parse_all(
+    paths: IndexMap<String, PathBuf>,
+) -> IndexMap<String, crate::Result<XdgDesktopFile>> {
+    let paths: Vec<(String, PathBuf)> = paths.into_iter().collect();
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = paths.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(id, path)| (id.clone(), XdgDesktopFile::from_path(path)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+impl Discovery {
+    /// Searches `$XDG_DATA_HOME/applications`, then `applications` in each of
+    /// `$XDG_DATA_DIRS`.
+    pub fn new() -> Discovery {
+        Discovery {
+            dirs: xdg_base_dirs::data_paths("applications"),
+        }
+    }
+
+    /// Searches the autostart directories instead: `$XDG_CONFIG_HOME/autostart`, then
+    /// `autostart` in each of `$XDG_CONFIG_DIRS`. A user's file shadows a system one with the
+    /// same name, which is how autostart entries are disabled or overridden; use
+    /// [`XdgDesktopFile::should_autostart`] to filter the loaded entries.
+    pub fn autostart() -> Discovery {
+        Discovery {
+            dirs: xdg_base_dirs::config_paths("autostart"),
+        }
+    }
+
+    /// Searches `dirs`, in order of decreasing precedence.
+    pub fn with_dirs<P: Into<PathBuf>>(dirs: impl IntoIterator<Item = P>) -> Discovery {
+        Discovery {
+            dirs: dirs.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Maps the ID of every installed desktop entry to the file that provides it, leaving out
+    /// shadowed files.
+    pub fn paths(&self) -> IndexMap<String, PathBuf> {
+        self.all_paths()
+            .into_iter()
+            .filter_map(|(id, paths)| Some((id, paths.into_iter().next()?)))
+            .collect()
+    }
+
+    /// Maps the ID of every installed desktop entry to all the files that provide it, in
+    /// order of precedence. The first file is the one in effect; the rest are shadowed by it.
+    pub fn all_paths(&self) -> IndexMap<String, Vec<PathBuf>> {
+        let mut out: IndexMap<String, Vec<PathBuf>> = IndexMap::new();
+        for dir in &self.dirs {
+            let mut found = Vec::new();
+            scan(dir, dir, &mut Vec::new(), &mut found);
+            for (id, path) in found {
+                out.entry(id).or_default().push(path);
+            }
+        }
+        out
+    }
+
+    /// Lists the files that are hidden by a file with the same ID earlier in the search path,
+    /// such as system entries overridden by a user's copy.
+    pub fn shadowed(&self) -> Vec<ShadowedFile> {
+        let mut out = Vec::new();
+        for (id, paths) in self.all_paths() {
+            let mut paths = paths.into_iter();
+            let effective = match paths.next() {
+                Some(p) => p,
+                None => continue,
+            };
+            out.extend(paths.map(|path| ShadowedFile {
+                id: id.clone(),
+                path,
+                shadowed_by: effective.clone(),
+            }));
+        }
+        out
+    }
+
+    /// Finds the file that provides the desktop entry with the ID `id`, such as
+    /// `org.kde.dolphin.desktop`, searching the directories in order. This is much cheaper
+    /// than [`paths`](Discovery::paths) when only a few IDs are needed.
+    pub fn find(&self, id: &str) -> Option<PathBuf> {
+        if id.is_empty() || id.contains('/') {
+            return None;
+        }
+        self.dirs.iter().find_map(|dir| resolve_id(dir, id))
+    }
+
+    /// Computes the desktop file ID of `path` relative to the first search directory that
+    /// contains it. The result doesn't depend on whether `path` exists, or is shadowed.
+    pub fn id_of(&self, path: impl AsRef<Path>) -> Option<String> {
+        self.dirs
+            .iter()
+            .find_map(|dir| desktop_file_id(dir, path.as_ref()))
+    }
+
+    /// Reads and parses every installed desktop entry, keyed by desktop file ID. A file that
+    /// can't be read or parsed is reported in place of its entry. Files are parsed in
+    /// parallel, on rayon's thread pool if the `rayon` feature is enabled.
+    pub fn load(&self) -> IndexMap<String, crate::Result<XdgDesktopFile>> {
+        parse_all(self.paths())
+    }
+}
+
+impl XdgDesktopFile {
+    /// Parses every `.desktop` file below `dir` in parallel, keyed by desktop file ID
+    /// relative to `dir`, as [`Discovery::load`] does for a single directory.
+    pub fn parse_dir(dir: impl AsRef<Path>) -> IndexMap<String, crate::Result<XdgDesktopFile>> {
+        Discovery::with_dirs([dir.as_ref()]).load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (user, system) = (root.join("user"), root.join("system"));
+        fs::create_dir_all(user.join("kde")).unwrap();
+        fs::create_dir_all(&system).unwrap();
+        let entry = |name: &str| format!("[Desktop Entry]\nType=Application\nName={}\n", name);
+        fs::write(user.join("kde/konsole.desktop"), entry("Konsole")).unwrap();
+        fs::write(user.join("htop.desktop"), entry("User htop")).unwrap();
+        fs::write(user.join("notes.txt"), "").unwrap();
+        fs::write(system.join("htop.desktop"), entry("htop")).unwrap();
+        fs::write(system.join("broken.desktop"), "Name=Broken\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+            symlink(&user, user.join("kde/up")).unwrap();
+            symlink(user.join("kde"), user.join("kde/self")).unwrap();
+        }
+
+        let discovery = Discovery::with_dirs(vec![&user, &system]);
+        let found = discovery.load();
+        let shadowed = discovery.shadowed();
+        let user_only = XdgDesktopFile::parse_dir(&user);
+        let ids: Vec<&str> = user_only.keys().map(String::as_str).collect();
+        assert_eq!(ids, ["htop.desktop", "kde-konsole.desktop"]);
+        let ids: Vec<&str> = found.keys().map(String::as_str).collect();
+        assert_eq!(
+            ids,
+            ["htop.desktop", "kde-konsole.desktop", "broken.desktop"]
+        );
+        let htop = found["htop.desktop"].as_ref().unwrap();
+        let name = htop.sections().next().unwrap().1.get("Name").unwrap();
+        assert_eq!(name.to_string(), "User htop");
+        assert!(found["broken.desktop"].is_err());
+        assert_eq!(
+            shadowed,
+            [ShadowedFile {
+                id: "htop.desktop".to_string(),
+                path: system.join("htop.desktop"),
+                shadowed_by: user.join("htop.desktop"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_desktop_file_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (user, system) = (root.join("user"), root.join("system"));
+        fs::create_dir_all(user.join("org/kde")).unwrap();
+        fs::create_dir_all(&system).unwrap();
+        fs::write(user.join("org/kde/dolphin.desktop"), "").unwrap();
+        fs::write(system.join("org.kde.dolphin.desktop"), "").unwrap();
+        fs::write(system.join("htop.desktop"), "").unwrap();
+        fs::write(root.join("outside.desktop"), "").unwrap();
+        let discovery = Discovery::with_dirs(vec![&user, &system]);
+        let found = (
+            discovery.find("org-kde-dolphin.desktop"),
+            discovery.find("org.kde.dolphin.desktop"),
+            discovery.find("htop.desktop"),
+            discovery.find("missing.desktop"),
+        );
+        let escaped =
+            ["..-outside.desktop", ".-htop.desktop", "-htop.desktop"].map(|id| discovery.find(id));
+        assert_eq!(found.0, Some(user.join("org/kde/dolphin.desktop")));
+        assert_eq!(found.1, Some(system.join("org.kde.dolphin.desktop")));
+        assert_eq!(found.2, Some(system.join("htop.desktop")));
+        assert_eq!(found.3, None);
+        assert_eq!(escaped, [None, None, None]);
+        let id = discovery.id_of(user.join("org/kde/dolphin.desktop"));
+        assert_eq!(id.as_deref(), Some("org-kde-dolphin.desktop"));
+        assert_eq!(discovery.id_of(root.join("elsewhere.desktop")), None);
+    }
+}
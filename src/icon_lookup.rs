@@ -0,0 +1,315 @@
+//! Resolution of icon names to files, following the
+//! [Icon Theme Specification](https://specifications.freedesktop.org/icon-theme-spec/latest/).
+//!
+//! Icons are looked up in the requested theme, then the themes it inherits from, then
+//! `hicolor`, and finally directly in the base directories (which include
+//! `/usr/share/pixmaps`). Within a theme, an icon from a directory matching the requested size
+//! and scale is preferred; otherwise the one from the directory closest in size is used.
+
+use crate::{xdg_base_dirs, KeyFile, XdgDesktopFile};
+use std::path::{Path, PathBuf};
+
+const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// A subdirectory of a theme, as described by its `index.theme`.
+#[derive(Debug, Clone)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDirectory {
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryType::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let wanted = size * scale;
+        let (min, max) = match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if wanted < min {
+            min - wanted
+        } else {
+            wanted.saturating_sub(max)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Theme {
+    name: String,
+    inherits: Vec<String>,
+    directories: Vec<ThemeDirectory>,
+}
+
+fn comma_list(s: Option<&str>) -> Vec<String> {
+    s.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Settings for looking up icons.
+#[derive(Debug, Clone)]
+pub struct IconLookup {
+    theme: String,
+    size: u32,
+    scale: u32,
+    base_dirs: Vec<PathBuf>,
+}
+
+impl IconLookup {
+    /// Looks up 48px icons at scale 1 in `theme`, searching `$HOME/.icons`, `icons` in each
+    /// XDG data directory, and `/usr/share/pixmaps`.
+    pub fn new(theme: &str) -> IconLookup {
+        let base_dirs = xdg_base_dirs::home()
+            .map(|h| h.join(".icons"))
+            .into_iter()
+            .chain(xdg_base_dirs::data_paths("icons"))
+            .chain(Some(PathBuf::from("/usr/share/pixmaps")))
+            .collect();
+        IconLookup {
+            theme: theme.to_string(),
+            size: 48,
+            scale: 1,
+            base_dirs,
+        }
+    }
+
+    /// The desired icon size, in logical pixels.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// The desired scale, e.g. 2 for an icon drawn at twice the size on a HiDPI display.
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Replaces the directories searched for themes and unthemed icons, in order of
+    /// decreasing precedence.
+    pub fn base_dirs<P: Into<PathBuf>>(mut self, dirs: impl IntoIterator<Item = P>) -> Self {
+        self.base_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Reads a theme's `index.theme` from the first base directory that has one.
+    fn load_theme(&self, name: &str) -> Option<Theme> {
+        let index = self
+            .base_dirs
+            .iter()
+            .map(|d| d.join(name).join("index.theme"))
+            .find(|p| p.is_file())?;
+        let file = KeyFile::from_path(index).ok()?;
+        let number = |section: &str, key: &str| {
+            file.raw_value(section, key)
+                .and_then(|n| n.trim().parse::<u32>().ok())
+        };
+        let mut paths = comma_list(file.raw_value("Icon Theme", "Directories"));
+        paths.extend(comma_list(
+            file.raw_value("Icon Theme", "ScaledDirectories"),
+        ));
+        let directories = paths
+            .into_iter()
+            .filter_map(|path| {
+                let size = number(&path, "Size")?;
+                let kind = match file.raw_value(&path, "Type") {
+                    Some("Fixed") => DirectoryType::Fixed,
+                    Some("Scalable") => DirectoryType::Scalable,
+                    _ => DirectoryType::Threshold,
+                };
+                Some(ThemeDirectory {
+                    size,
+                    scale: number(&path, "Scale").unwrap_or(1),
+                    kind,
+                    min_size: number(&path, "MinSize").unwrap_or(size),
+                    max_size: number(&path, "MaxSize").unwrap_or(size),
+                    threshold: number(&path, "Threshold").unwrap_or(2),
+                    path,
+                })
+            })
+            .collect();
+        Some(Theme {
+            name: name.to_string(),
+            inherits: comma_list(file.raw_value("Icon Theme", "Inherits")),
+            directories,
+        })
+    }
+
+    fn find_file(&self, dir: &Path, icon: &str) -> Option<PathBuf> {
+        EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", icon, ext)))
+            .find(|p| p.is_file())
+    }
+
+    /// Looks up `icon` in `theme` alone, without its parents.
+    fn lookup_in(&self, theme: &Theme, icon: &str) -> Option<PathBuf> {
+        let candidates = |dir: &ThemeDirectory| {
+            self.base_dirs
+                .iter()
+                .find_map(|base| self.find_file(&base.join(&theme.name).join(&dir.path), icon))
+        };
+        if let Some(found) = theme
+            .directories
+            .iter()
+            .filter(|d| d.matches(self.size, self.scale))
+            .find_map(candidates)
+        {
+            return Some(found);
+        }
+        let mut closest: Option<(u32, PathBuf)> = None;
+        for dir in &theme.directories {
+            let distance = dir.distance(self.size, self.scale);
+            if closest.as_ref().is_some_and(|(d, _)| *d <= distance) {
+                continue;
+            }
+            if let Some(found) = candidates(dir) {
+                closest = Some((distance, found));
+            }
+        }
+        closest.map(|(_, path)| path)
+    }
+
+    /// Looks up `icon` in `theme` and the themes it inherits from, depth first.
+    fn lookup_with_parents(
+        &self,
+        theme: &str,
+        icon: &str,
+        visited: &mut Vec<String>,
+    ) -> Option<PathBuf> {
+        if visited.iter().any(|t| t == theme) {
+            return None;
+        }
+        visited.push(theme.to_string());
+        let theme = self.load_theme(theme)?;
+        if let Some(found) = self.lookup_in(&theme, icon) {
+            return Some(found);
+        }
+        theme
+            .inherits
+            .iter()
+            .find_map(|parent| self.lookup_with_parents(parent, icon, visited))
+    }
+
+    /// Resolves an icon name, as found in an `Icon` key, to a file. Absolute paths are
+    /// returned as they are if the file exists.
+    pub fn find(&self, icon: &str) -> Option<PathBuf> {
+        let path = Path::new(icon);
+        if path.is_absolute() {
+            return Some(path.to_path_buf()).filter(|p| p.is_file());
+        }
+        let mut visited = Vec::new();
+        self.lookup_with_parents(&self.theme, icon, &mut visited)
+            .or_else(|| self.lookup_with_parents("hicolor", icon, &mut visited))
+            .or_else(|| {
+                self.base_dirs
+                    .iter()
+                    .find_map(|base| self.find_file(base, icon))
+            })
+    }
+}
+
+impl XdgDesktopFile {
+    /// Resolves the entry's `Icon` to a file; see [`IconLookup::find`].
+    pub fn icon_path(&self, lookup: &IconLookup) -> Option<PathBuf> {
+        lookup.find(self.get_str("Desktop Entry", "Icon")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_find() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (icons, pixmaps) = (root.join("icons"), root.join("pixmaps"));
+        let theme = |name: &str, index: &str, files: &[&str]| {
+            fs::create_dir_all(icons.join(name)).unwrap();
+            fs::write(icons.join(name).join("index.theme"), index).unwrap();
+            for f in files {
+                let path = icons.join(name).join(f);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, "").unwrap();
+            }
+        };
+        theme(
+            "Custom",
+            "[Icon Theme]\nName=Custom\nInherits=Parent\nDirectories=16x16/apps,64x64/apps\n\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\n[64x64/apps]\nSize=64\nType=Fixed\n",
+            &["16x16/apps/term.png", "64x64/apps/term.png"],
+        );
+        theme(
+            "Parent",
+            "[Icon Theme]\nName=Parent\nDirectories=scalable/apps\n\n\
+             [scalable/apps]\nSize=48\nType=Scalable\nMinSize=8\nMaxSize=512\n",
+            &["scalable/apps/editor.svg"],
+        );
+        theme(
+            "hicolor",
+            "[Icon Theme]\nName=Hicolor\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\n",
+            &["48x48/apps/calc.png"],
+        );
+        fs::create_dir_all(&pixmaps).unwrap();
+        fs::write(pixmaps.join("legacy.xpm"), "").unwrap();
+
+        let lookup = IconLookup::new("Custom").base_dirs([&icons, &pixmaps]);
+        let found = |icon: &str, size: u32| {
+            let path = lookup.clone().size(size).find(icon)?;
+            Some(path.strip_prefix(root).unwrap().to_path_buf())
+        };
+        assert_eq!(
+            found("term", 16),
+            Some("icons/Custom/16x16/apps/term.png".into())
+        );
+        assert_eq!(
+            found("term", 48),
+            Some("icons/Custom/64x64/apps/term.png".into())
+        );
+        assert_eq!(
+            found("editor", 48),
+            Some("icons/Parent/scalable/apps/editor.svg".into())
+        );
+        assert_eq!(
+            found("calc", 48),
+            Some("icons/hicolor/48x48/apps/calc.png".into())
+        );
+        assert_eq!(found("legacy", 48), Some("pixmaps/legacy.xpm".into()));
+        assert_eq!(found("missing", 48), None);
+    }
+}
@@ -1,8 +1,134 @@
+// Without the `std` feature, only the parsing of the file format itself is available:
+// `KeyFile`, `SectionName` and `XdgParseError`, which need no more than `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod database;
+#[cfg(feature = "std")]
+pub mod discovery;
+#[cfg(feature = "icon-lookup")]
+pub mod icon_lookup;
+#[cfg(feature = "std")]
+pub mod menu;
+#[cfg(feature = "std")]
+pub mod mimeapps;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "std")]
+pub mod trashinfo;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "std")]
+mod xdg_base_dirs;
+#[cfg(feature = "std")]
+mod xdg_cache;
+#[cfg(feature = "std")]
+mod xdg_category;
+#[cfg(feature = "dbus")]
+mod xdg_dbus;
+#[cfg(feature = "std")]
+mod xdg_desktop_entry_builder;
+#[cfg(feature = "std")]
 mod xdg_desktop_file;
+#[cfg(feature = "std")]
+mod xdg_desktop_file_ref;
+#[cfg(feature = "std")]
+mod xdg_desktop_section;
+#[cfg(feature = "std")]
 mod xdg_desktop_value;
+#[cfg(feature = "std")]
+mod xdg_diff;
+#[cfg(feature = "std")]
+mod xdg_directory_entry;
+#[cfg(feature = "std")]
+mod xdg_entry_type;
+#[cfg(feature = "std")]
+mod xdg_exec;
+mod xdg_key_file;
+#[cfg(feature = "std")]
+mod xdg_key_table;
+#[cfg(feature = "launch")]
+mod xdg_launch;
+#[cfg(feature = "std")]
+mod xdg_launch_hints;
+#[cfg(feature = "url")]
+mod xdg_link;
+#[cfg(feature = "std")]
+mod xdg_merge;
+#[cfg(feature = "std")]
+mod xdg_mime_type;
 mod xdg_parse_error;
+#[cfg(feature = "std")]
+mod xdg_parse_report;
+#[cfg(feature = "std")]
+mod xdg_parse_warning;
+#[cfg(feature = "std")]
+mod xdg_parser_options;
+#[cfg(feature = "std")]
+mod xdg_path_bytes;
+mod xdg_section_name;
+#[cfg(feature = "std")]
+mod xdg_spec_version;
+#[cfg(feature = "std")]
+mod xdg_standard_key;
+#[cfg(feature = "std")]
+mod xdg_template;
+#[cfg(feature = "std")]
+mod xdg_validate;
+#[cfg(feature = "std")]
+mod xdg_wm_class;
 
-pub type Result<T> = std::result::Result<T, XdgParseError>;
+pub type Result<T> = core::result::Result<T, XdgParseError>;
+#[cfg(feature = "std")]
+pub use xdg_category::Category;
+#[cfg(feature = "dbus")]
+pub use xdg_dbus::Activation;
+#[cfg(feature = "std")]
+pub use xdg_desktop_entry_builder::XdgDesktopEntryBuilder;
+#[cfg(feature = "std")]
 pub use xdg_desktop_file::XdgDesktopFile;
-pub use xdg_desktop_value::XdgDesktopValue;
+#[cfg(feature = "std")]
+pub use xdg_desktop_file_ref::XdgDesktopFileRef;
+#[cfg(feature = "std")]
+pub use xdg_desktop_section::XdgDesktopSection;
+#[cfg(feature = "std")]
+pub use xdg_desktop_value::{KeyType, XdgDesktopValue};
+#[cfg(feature = "std")]
+pub use xdg_diff::DiffEntry;
+#[cfg(feature = "std")]
+pub use xdg_directory_entry::XdgDirectoryEntry;
+#[cfg(feature = "std")]
+pub use xdg_entry_type::EntryType;
+#[cfg(feature = "std")]
+pub use xdg_exec::{ExecCommand, FieldCodes};
+pub use xdg_key_file::KeyFile;
+#[cfg(feature = "std")]
+pub use xdg_key_table::KeyTable;
+#[cfg(feature = "launch")]
+pub use xdg_launch::{new_startup_id, LaunchOptions, Launched};
+#[cfg(feature = "std")]
+pub use xdg_launch_hints::LaunchHints;
+#[cfg(feature = "std")]
+pub use xdg_merge::ListMerge;
+#[cfg(feature = "std")]
+pub use xdg_mime_type::MimeType;
 pub use xdg_parse_error::XdgParseError;
+#[cfg(feature = "std")]
+pub use xdg_parse_report::ParseReport;
+#[cfg(feature = "std")]
+pub use xdg_parse_warning::XdgParseWarning;
+#[cfg(feature = "std")]
+pub use xdg_parser_options::{
+    Duplicates, KeyTyper, MalformedLines, ParserOptions, UnknownKeys, UnknownSections,
+};
+pub use xdg_section_name::{SectionKind, SectionName};
+#[cfg(feature = "std")]
+pub use xdg_spec_version::SpecVersion;
+#[cfg(feature = "std")]
+pub use xdg_standard_key::{Key, StandardKey};
+#[cfg(feature = "std")]
+pub use xdg_wm_class::WmClassMatch;
@@ -0,0 +1,536 @@
+//! Application menus, as described by the
+//! [Desktop Menu Specification](https://specifications.freedesktop.org/menu-spec/latest/).
+//!
+//! A `.menu` file is parsed into a [`MenuDefinition`], which [`MenuDefinition::resolve`]
+//! turns into a tree of [`Menu`]s by matching its rules against the desktop entries in its
+//! application directories. `<Include>`, `<Exclude>`, `<OnlyUnallocated>`, `<Deleted>` and
+//! the `<MergeFile>`/`<MergeDir>` family are supported; `<LegacyDir>`, `<Move>` and `<Layout>`
+//! are ignored, and submenus are listed in the order they are defined.
+
+use crate::{
+    discovery::Discovery, xdg_base_dirs, XdgDesktopFile, XdgDirectoryEntry, XdgParseError,
+};
+use indexmap::{IndexMap, IndexSet};
+use roxmltree::{Document, Node, ParsingOptions};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A condition matched against desktop entries by `<Include>` and `<Exclude>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+    /// Matches the entry with this desktop file ID.
+    Filename(String),
+    /// Matches entries listing this category.
+    Category(String),
+    All,
+    And(Vec<Matcher>),
+    Or(Vec<Matcher>),
+    /// Matches entries that match none of the contained conditions.
+    Not(Vec<Matcher>),
+}
+
+/// Adds entries matching any of the conditions to a menu, or removes them from it. Rules are
+/// applied in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuRule {
+    Include(Vec<Matcher>),
+    Exclude(Vec<Matcher>),
+}
+
+/// A `<Menu>` element, with any merged files folded in and same-named submenus combined.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MenuDefinition {
+    pub name: String,
+    /// `.directory` files naming and describing the menu; the last that exists is used.
+    pub directories: Vec<String>,
+    /// Directories of desktop entries available to this menu and its submenus. Later
+    /// directories take precedence.
+    pub app_dirs: Vec<PathBuf>,
+    /// Directories searched for `directories`. Later directories take precedence.
+    pub directory_dirs: Vec<PathBuf>,
+    pub rules: Vec<MenuRule>,
+    /// Whether only entries not already placed in some other menu may be included.
+    pub only_unallocated: Option<bool>,
+    pub deleted: Option<bool>,
+    pub submenus: Vec<MenuDefinition>,
+}
+
+/// A desktop entry placed in a menu.
+#[derive(Debug, Clone)]
+pub struct MenuEntry {
+    pub id: String,
+    pub path: PathBuf,
+    pub file: Arc<XdgDesktopFile>,
+}
+
+/// A resolved menu. Menus without entries, either directly or in their submenus, are left
+/// out, as are deleted ones.
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub name: String,
+    /// The menu's `.directory` file, providing its displayed name, icon and comment.
+    pub directory: Option<Arc<XdgDirectoryEntry>>,
+    /// The menu's entries, sorted by desktop file ID.
+    pub entries: Vec<MenuEntry>,
+    pub submenus: Vec<Menu>,
+}
+
+fn menu_error(e: impl ToString) -> XdgParseError {
+    XdgParseError::InvalidMenu(e.to_string())
+}
+
+fn text(node: Node) -> String {
+    node.text().unwrap_or_default().trim().to_string()
+}
+
+fn parse_matchers(node: Node) -> Vec<Matcher> {
+    node.children()
+        .filter(Node::is_element)
+        .filter_map(|child| match child.tag_name().name() {
+            "Filename" => Some(Matcher::Filename(text(child))),
+            "Category" => Some(Matcher::Category(text(child))),
+            "All" => Some(Matcher::All),
+            "And" => Some(Matcher::And(parse_matchers(child))),
+            "Or" => Some(Matcher::Or(parse_matchers(child))),
+            "Not" => Some(Matcher::Not(parse_matchers(child))),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Matcher {
+    fn matches(&self, id: &str, file: &XdgDesktopFile) -> bool {
+        match self {
+            Matcher::Filename(f) => f == id,
+            Matcher::Category(c) => file.categories().iter().any(|cat| cat.as_str() == c),
+            Matcher::All => true,
+            Matcher::And(m) => m.iter().all(|m| m.matches(id, file)),
+            Matcher::Or(m) => m.iter().any(|m| m.matches(id, file)),
+            Matcher::Not(m) => !m.iter().any(|m| m.matches(id, file)),
+        }
+    }
+}
+
+/// Reads `.menu` files, keeping track of the ones being read to break merge cycles.
+struct Loader {
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn load_file(&mut self, path: &Path) -> crate::Result<MenuDefinition> {
+        let canonical = path.canonicalize()?;
+        if self.stack.contains(&canonical) {
+            return Ok(MenuDefinition::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        self.stack.push(canonical);
+        let base = path.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        let result = self.load_str(&contents, &base);
+        self.stack.pop();
+        result
+    }
+
+    fn load_str(&mut self, s: &str, base: &Path) -> crate::Result<MenuDefinition> {
+        let options = ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        };
+        let doc = Document::parse_with_options(s, options).map_err(menu_error)?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "Menu" {
+            return Err(menu_error("root element is not <Menu>"));
+        }
+        self.parse_menu(root, base)
+    }
+
+    /// Folds the menus of `path`, or of every `.menu` file in it if it is a directory, into
+    /// `menu`. Files that can't be read are skipped, as the specification requires.
+    fn merge_path(&mut self, menu: &mut MenuDefinition, path: &Path) {
+        let files = if path.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(path)
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "menu"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        for file in files {
+            if let Ok(merged) = self.load_file(&file) {
+                menu.absorb(merged);
+            }
+        }
+    }
+
+    fn parse_menu(&mut self, node: Node, base: &Path) -> crate::Result<MenuDefinition> {
+        let mut menu = MenuDefinition::default();
+        for child in node.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                "Name" => menu.name = text(child),
+                "Directory" => menu.directories.push(text(child)),
+                "AppDir" => menu.app_dirs.push(base.join(text(child))),
+                "DefaultAppDirs" => {
+                    let mut dirs = xdg_base_dirs::data_paths("applications");
+                    dirs.reverse();
+                    menu.app_dirs.extend(dirs);
+                }
+                "DirectoryDir" => menu.directory_dirs.push(base.join(text(child))),
+                "DefaultDirectoryDirs" => {
+                    let mut dirs = xdg_base_dirs::data_paths("desktop-directories");
+                    dirs.reverse();
+                    menu.directory_dirs.extend(dirs);
+                }
+                "Include" => menu.rules.push(MenuRule::Include(parse_matchers(child))),
+                "Exclude" => menu.rules.push(MenuRule::Exclude(parse_matchers(child))),
+                "OnlyUnallocated" => menu.only_unallocated = Some(true),
+                "NotOnlyUnallocated" => menu.only_unallocated = Some(false),
+                "Deleted" => menu.deleted = Some(true),
+                "NotDeleted" => menu.deleted = Some(false),
+                "Menu" => {
+                    let submenu = self.parse_menu(child, base)?;
+                    menu.submenus.push(submenu);
+                }
+                // `type="parent"` merges aren't supported.
+                "MergeFile" if child.attribute("type") != Some("parent") => {
+                    self.merge_path(&mut menu, &base.join(text(child)))
+                }
+                "MergeDir" => self.merge_path(&mut menu, &base.join(text(child))),
+                "DefaultMergeDirs" => {
+                    let mut dirs = xdg_base_dirs::config_paths("menus/applications-merged");
+                    dirs.reverse();
+                    for dir in dirs {
+                        self.merge_path(&mut menu, &dir);
+                    }
+                }
+                _ => {}
+            }
+        }
+        menu.combine_submenus();
+        Ok(menu)
+    }
+}
+
+impl MenuDefinition {
+    /// Parses a menu file. Relative paths in it, and in the files it merges, are resolved
+    /// against the current directory.
+    pub fn parse(s: &str) -> crate::Result<MenuDefinition> {
+        Loader { stack: Vec::new() }.load_str(s, Path::new(""))
+    }
+
+    /// Reads and parses a menu file. Relative paths are resolved against its directory.
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<MenuDefinition> {
+        Loader { stack: Vec::new() }.load_file(path.as_ref())
+    }
+
+    /// Reads the system's main menu: the first `menus/${XDG_MENU_PREFIX}applications.menu`
+    /// in the XDG configuration directories.
+    pub fn load() -> crate::Result<MenuDefinition> {
+        let file = format!(
+            "menus/{}applications.menu",
+            env::var("XDG_MENU_PREFIX").unwrap_or_default()
+        );
+        let path = xdg_base_dirs::config_paths(&file)
+            .into_iter()
+            .find(|p| p.is_file())
+            .ok_or_else(|| menu_error(format!("no {} found", file)))?;
+        MenuDefinition::from_path(path)
+    }
+
+    /// Appends the contents of `other` to this menu, keeping this menu's name.
+    fn absorb(&mut self, other: MenuDefinition) {
+        self.directories.extend(other.directories);
+        self.app_dirs.extend(other.app_dirs);
+        self.directory_dirs.extend(other.directory_dirs);
+        self.rules.extend(other.rules);
+        self.only_unallocated = other.only_unallocated.or(self.only_unallocated);
+        self.deleted = other.deleted.or(self.deleted);
+        self.submenus.extend(other.submenus);
+        self.combine_submenus();
+    }
+
+    /// Merges submenus sharing a name into the first of them.
+    fn combine_submenus(&mut self) {
+        let mut combined: IndexMap<String, MenuDefinition> = IndexMap::new();
+        for submenu in self.submenus.drain(..) {
+            match combined.get_mut(&submenu.name) {
+                Some(existing) => existing.absorb(submenu),
+                None => {
+                    combined.insert(submenu.name.clone(), submenu);
+                }
+            }
+        }
+        self.submenus = combined.into_values().collect();
+    }
+
+    /// Builds the menu tree by reading the desktop entries in each menu's application
+    /// directories and applying its rules. Entries with `Hidden=true` are treated as deleted;
+    /// other visibility checks such as [`XdgDesktopFile::is_shown`] are left to the caller.
+    pub fn resolve(&self) -> Menu {
+        let mut resolver = Resolver {
+            files: IndexMap::new(),
+            allocated: IndexSet::new(),
+        };
+        let pool = IndexMap::new();
+        // Menus with <OnlyUnallocated> are filled in once every other menu has claimed its
+        // entries.
+        let first = resolver.resolve(self, &pool, false);
+        let second = resolver.resolve(self, &pool, true);
+        resolver
+            .build(self, merge_selections(first, second), &[])
+            .unwrap_or_else(|| Menu {
+                name: self.name.clone(),
+                directory: None,
+                entries: Vec::new(),
+                submenus: Vec::new(),
+            })
+    }
+}
+
+/// The entries chosen for a menu and its submenus, by desktop file ID.
+struct Selection {
+    entries: Vec<MenuEntry>,
+    submenus: Vec<Selection>,
+}
+
+struct Resolver {
+    /// Desktop files read so far, by path; `None` for unreadable or hidden ones.
+    files: IndexMap<PathBuf, Option<Arc<XdgDesktopFile>>>,
+    allocated: IndexSet<String>,
+}
+
+impl Resolver {
+    fn file(&mut self, path: &Path) -> Option<Arc<XdgDesktopFile>> {
+        self.files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let file = XdgDesktopFile::from_path(path).ok()?;
+                let hidden = file.get_bool("Desktop Entry", "Hidden") == Some(true);
+                (!hidden).then(|| Arc::new(file))
+            })
+            .clone()
+    }
+
+    /// The entries available to `menu`: those inherited from its parents, overridden by
+    /// those in its own application directories.
+    fn pool(
+        &self,
+        menu: &MenuDefinition,
+        inherited: &IndexMap<String, PathBuf>,
+    ) -> IndexMap<String, PathBuf> {
+        let mut pool = inherited.clone();
+        // Discovery gives earlier directories precedence; menus give it to later ones.
+        let dirs: Vec<&PathBuf> = menu.app_dirs.iter().rev().collect();
+        for (id, path) in Discovery::with_dirs(dirs).paths() {
+            pool.insert(id, path);
+        }
+        pool
+    }
+
+    fn select(
+        &mut self,
+        menu: &MenuDefinition,
+        pool: &IndexMap<String, PathBuf>,
+    ) -> Vec<MenuEntry> {
+        let mut chosen: IndexMap<String, MenuEntry> = IndexMap::new();
+        for rule in &menu.rules {
+            let (matchers, include) = match rule {
+                MenuRule::Include(m) => (m, true),
+                MenuRule::Exclude(m) => (m, false),
+            };
+            for (id, path) in pool {
+                let file = match self.file(path) {
+                    Some(f) => f,
+                    None => continue,
+                };
+                if !matchers.iter().any(|m| m.matches(id, &file)) {
+                    continue;
+                }
+                if include {
+                    chosen.entry(id.clone()).or_insert_with(|| MenuEntry {
+                        id: id.clone(),
+                        path: path.clone(),
+                        file,
+                    });
+                } else {
+                    chosen.shift_remove(id);
+                }
+            }
+        }
+        let mut entries: Vec<MenuEntry> = chosen.into_values().collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        entries
+    }
+
+    fn resolve(
+        &mut self,
+        menu: &MenuDefinition,
+        inherited: &IndexMap<String, PathBuf>,
+        unallocated_pass: bool,
+    ) -> Selection {
+        // Deleted menus mustn't claim entries other menus could otherwise show.
+        if menu.deleted.unwrap_or(false) {
+            return Selection {
+                entries: Vec::new(),
+                submenus: Vec::new(),
+            };
+        }
+        let pool = self.pool(menu, inherited);
+        let entries = if menu.only_unallocated.unwrap_or(false) == unallocated_pass {
+            let mut entries = self.select(menu, &pool);
+            if unallocated_pass {
+                entries.retain(|e| !self.allocated.contains(&e.id));
+            } else {
+                self.allocated.extend(entries.iter().map(|e| e.id.clone()));
+            }
+            entries
+        } else {
+            Vec::new()
+        };
+        let submenus = menu
+            .submenus
+            .iter()
+            .map(|s| self.resolve(s, &pool, unallocated_pass))
+            .collect();
+        Selection { entries, submenus }
+    }
+
+    fn build(
+        &mut self,
+        menu: &MenuDefinition,
+        selection: Selection,
+        inherited_dirs: &[PathBuf],
+    ) -> Option<Menu> {
+        if menu.deleted.unwrap_or(false) {
+            return None;
+        }
+        let dirs: Vec<PathBuf> = inherited_dirs
+            .iter()
+            .chain(&menu.directory_dirs)
+            .cloned()
+            .collect();
+        let submenus: Vec<Menu> = menu
+            .submenus
+            .iter()
+            .zip(selection.submenus)
+            .filter_map(|(m, s)| self.build(m, s, &dirs))
+            .collect();
+        if selection.entries.is_empty() && submenus.is_empty() {
+            return None;
+        }
+        Some(Menu {
+            name: menu.name.clone(),
+            directory: find_directory(menu, &dirs),
+            entries: selection.entries,
+            submenus,
+        })
+    }
+}
+
+/// The last of `menu`'s `.directory` files found in `dirs`, later directories taking precedence.
+fn find_directory(menu: &MenuDefinition, dirs: &[PathBuf]) -> Option<Arc<XdgDirectoryEntry>> {
+    menu.directories.iter().rev().find_map(|name| {
+        dirs.iter()
+            .rev()
+            .map(|dir| dir.join(name))
+            .find(|p| p.is_file())
+            .and_then(|p| XdgDirectoryEntry::from_path(p).ok())
+            .map(Arc::new)
+    })
+}
+
+/// Combines the selections of the two passes; each menu only gets entries in one of them.
+fn merge_selections(mut first: Selection, second: Selection) -> Selection {
+    first.entries.extend(second.entries);
+    first.entries.sort_by(|a, b| a.id.cmp(&b.id));
+    first.submenus = first
+        .submenus
+        .into_iter()
+        .zip(second.submenus)
+        .map(|(a, b)| merge_selections(a, b))
+        .collect();
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (apps, dirs, merged) = (root.join("apps"), root.join("dirs"), root.join("merged"));
+        for dir in [&apps, &dirs, &merged] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let entry = |categories: &str| {
+            format!(
+                "[Desktop Entry]\nType=Application\nName=App\nExec=true\nCategories={}\n",
+                categories
+            )
+        };
+        fs::write(apps.join("editor.desktop"), entry("Development;")).unwrap();
+        fs::write(apps.join("ide.desktop"), entry("Development;Utility;")).unwrap();
+        fs::write(apps.join("calc.desktop"), entry("Utility;")).unwrap();
+        fs::write(
+            apps.join("hidden.desktop"),
+            entry("Utility;") + "Hidden=true\n",
+        )
+        .unwrap();
+        fs::write(
+            dirs.join("dev.directory"),
+            "[Desktop Entry]\nType=Directory\nName=Programming\n",
+        )
+        .unwrap();
+        fs::write(
+            merged.join("extra.menu"),
+            "<Menu><Name>Applications</Name>\
+             <Menu><Name>Games</Name><Include><All/></Include><Deleted/></Menu></Menu>",
+        )
+        .unwrap();
+        fs::write(
+            root.join("applications.menu"),
+            r#"<!DOCTYPE Menu PUBLIC "-//freedesktop//DTD Menu 1.0//EN"
+ "http://www.freedesktop.org/standards/menu-spec/menu-1.0.dtd">
+<Menu>
+  <Name>Applications</Name>
+  <AppDir>apps</AppDir>
+  <DirectoryDir>dirs</DirectoryDir>
+  <MergeDir>merged</MergeDir>
+  <Menu>
+    <Name>Development</Name>
+    <Directory>dev.directory</Directory>
+    <Include><Category>Development</Category></Include>
+  </Menu>
+  <Menu>
+    <Name>Accessories</Name>
+    <OnlyUnallocated/>
+    <Include><Category>Utility</Category></Include>
+  </Menu>
+  <Menu>
+    <Name>Development</Name>
+    <Exclude><And><Category>Development</Category><Category>Utility</Category></And></Exclude>
+  </Menu>
+  <Menu><Name>Empty</Name></Menu>
+</Menu>"#,
+        )
+        .unwrap();
+
+        let definition = MenuDefinition::from_path(root.join("applications.menu")).unwrap();
+        let menu = definition.resolve();
+        let names: Vec<&str> = menu.submenus.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["Development", "Accessories"]);
+        let ids = |m: &Menu| m.entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&menu.submenus[0]), ["editor.desktop"]);
+        assert_eq!(ids(&menu.submenus[1]), ["calc.desktop", "ide.desktop"]);
+        let directory = menu.submenus[0].directory.as_ref().unwrap();
+        assert_eq!(directory.name(), Some("Programming"));
+        assert!(MenuDefinition::parse("<Name>Nope</Name>").is_err());
+    }
+}
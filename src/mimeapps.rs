@@ -0,0 +1,259 @@
+//! Default applications and MIME type associations, as described by the
+//! [MIME Applications Associations specification](https://specifications.freedesktop.org/mime-apps-spec/latest/).
+//!
+//! `mimeapps.list` files are read into [`MimeApps`] and `mimeinfo.cache` files into
+//! [`MimeInfoCache`]. [`MimeApps::load`] reads and merges every `mimeapps.list` on the XDG
+//! search path.
+
+use crate::{xdg_base_dirs, KeyFile, XdgParseError};
+use indexmap::IndexMap;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Maps MIME types to lists of desktop file IDs.
+pub type Associations = IndexMap<String, Vec<String>>;
+
+/// The contents of one or more `mimeapps.list` files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeApps {
+    /// `[Default Applications]`: the preferred applications for each MIME type, in order.
+    pub default_applications: Associations,
+    /// `[Added Associations]`
+    pub added_associations: Associations,
+    /// `[Removed Associations]`
+    pub removed_associations: Associations,
+}
+
+/// The contents of a `mimeinfo.cache` file, which lists the applications that declare support
+/// for each MIME type through their `MimeType` key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeInfoCache {
+    /// `[MIME Cache]`
+    pub mime_types: Associations,
+}
+
+/// Reads the associations in `section`, whose keys are MIME types and whose values are lists
+/// of desktop file IDs.
+fn associations(file: &KeyFile, section: &str) -> Associations {
+    file.keys(section)
+        .map(|mime| {
+            let ids = file.string_list(section, mime).unwrap_or_default();
+            let ids = ids.into_iter().filter(|id| !id.is_empty()).collect();
+            (mime.to_string(), ids)
+        })
+        .collect()
+}
+
+/// Appends the IDs in `ids` that `list` doesn't already contain.
+fn extend_unique(list: &mut Vec<String>, ids: &[String]) {
+    for id in ids {
+        if !list.contains(id) {
+            list.push(id.clone());
+        }
+    }
+}
+
+fn is_removed(removed: &Associations, mime: &str, id: &str) -> bool {
+    removed
+        .get(mime)
+        .is_some_and(|ids| ids.iter().any(|r| r == id))
+}
+
+impl FromStr for MimeApps {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<MimeApps> {
+        let file = KeyFile::from_str(s)?;
+        Ok(MimeApps {
+            default_applications: associations(&file, "Default Applications"),
+            added_associations: associations(&file, "Added Associations"),
+            removed_associations: associations(&file, "Removed Associations"),
+        })
+    }
+}
+
+impl MimeApps {
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<MimeApps> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// The locations of `mimeapps.list` files in order of decreasing precedence: the
+    /// configuration directories, then the legacy `applications` data directories, each
+    /// consulting desktop-specific `$desktop-mimeapps.list` files (for the desktops in
+    /// `$XDG_CURRENT_DESKTOP`) before the generic one.
+    pub fn search_paths() -> Vec<PathBuf> {
+        let desktops = xdg_base_dirs::current_desktops();
+        let dirs = xdg_base_dirs::config_paths("")
+            .into_iter()
+            .chain(xdg_base_dirs::data_paths("applications"));
+        let mut out = Vec::new();
+        for dir in dirs {
+            for desktop in &desktops {
+                out.push(dir.join(format!("{}-mimeapps.list", desktop.to_lowercase())));
+            }
+            out.push(dir.join("mimeapps.list"));
+        }
+        out
+    }
+
+    /// Reads and merges every `mimeapps.list` found in [`search_paths`](MimeApps::search_paths).
+    pub fn load() -> MimeApps {
+        MimeApps::load_from(MimeApps::search_paths())
+    }
+
+    /// Reads and merges the files at `paths`, which must be in order of decreasing precedence.
+    /// Files that are missing or can't be parsed are skipped.
+    pub fn load_from<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> MimeApps {
+        MimeApps::merge(
+            paths
+                .into_iter()
+                .filter_map(|p| MimeApps::from_path(p).ok()),
+        )
+    }
+
+    /// Merges files given in order of decreasing precedence:
+    ///
+    /// * each MIME type's default applications are those of the higher-precedence files
+    ///   followed by those of the lower-precedence ones;
+    /// * associations are added by every file, except where removed by a file of higher
+    ///   precedence;
+    /// * removals accumulate, so they can be applied to a [`MimeInfoCache`] later.
+    pub fn merge(files: impl IntoIterator<Item = MimeApps>) -> MimeApps {
+        let mut out = MimeApps::default();
+        for file in files {
+            for (mime, ids) in &file.default_applications {
+                extend_unique(
+                    out.default_applications.entry(mime.clone()).or_default(),
+                    ids,
+                );
+            }
+            for (mime, ids) in &file.added_associations {
+                let kept: Vec<String> = ids
+                    .iter()
+                    .filter(|id| !is_removed(&out.removed_associations, mime, id))
+                    .cloned()
+                    .collect();
+                extend_unique(
+                    out.added_associations.entry(mime.clone()).or_default(),
+                    &kept,
+                );
+            }
+            for (mime, ids) in &file.removed_associations {
+                extend_unique(
+                    out.removed_associations.entry(mime.clone()).or_default(),
+                    ids,
+                );
+            }
+        }
+        out
+    }
+
+    /// The most preferred default application for `mime`, if any. Note that the
+    /// specification has callers skip defaults that aren't installed, so the other entries of
+    /// [`default_applications`](MimeApps::default_applications) may be needed.
+    pub fn default_for(&self, mime: &str) -> Option<&str> {
+        self.default_applications
+            .get(mime)
+            .and_then(|ids| ids.first())
+            .map(String::as_str)
+    }
+
+    /// The applications associated with `mime`: the added associations followed by those
+    /// from `cache` that haven't been removed.
+    pub fn associations_for(&self, mime: &str, cache: Option<&MimeInfoCache>) -> Vec<String> {
+        let mut out = self
+            .added_associations
+            .get(mime)
+            .cloned()
+            .unwrap_or_default();
+        if let Some(ids) = cache.and_then(|c| c.mime_types.get(mime)) {
+            let kept: Vec<String> = ids
+                .iter()
+                .filter(|id| !is_removed(&self.removed_associations, mime, id))
+                .cloned()
+                .collect();
+            extend_unique(&mut out, &kept);
+        }
+        out
+    }
+}
+
+impl FromStr for MimeInfoCache {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<MimeInfoCache> {
+        let file = KeyFile::from_str(s)?;
+        Ok(MimeInfoCache {
+            mime_types: associations(&file, "MIME Cache"),
+        })
+    }
+}
+
+impl MimeInfoCache {
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<MimeInfoCache> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Reads and combines the `mimeinfo.cache` of every `applications` data directory.
+    pub fn load() -> MimeInfoCache {
+        let mut out = MimeInfoCache::default();
+        for path in xdg_base_dirs::data_paths("applications/mimeinfo.cache") {
+            if let Ok(cache) = MimeInfoCache::from_path(path) {
+                for (mime, ids) in &cache.mime_types {
+                    extend_unique(out.mime_types.entry(mime.clone()).or_default(), ids);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn applications_for(&self, mime: &str) -> &[String] {
+        self.mime_types.get(mime).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: &str = "[Default Applications]
+text/html=firefox.desktop;chromium.desktop;
+
+[Removed Associations]
+image/png=gimp.desktop;
+";
+
+    const SYSTEM: &str = "[Default Applications]
+text/html=epiphany.desktop
+image/png=eog.desktop;
+
+[Added Associations]
+image/png=gimp.desktop;eog.desktop;
+";
+
+    #[test]
+    fn test_merge() {
+        let user: MimeApps = USER.parse().unwrap();
+        let system: MimeApps = SYSTEM.parse().unwrap();
+        assert_eq!(system.default_for("text/html"), Some("epiphany.desktop"));
+        let merged = MimeApps::merge(vec![user, system]);
+        assert_eq!(merged.default_for("text/html"), Some("firefox.desktop"));
+        assert_eq!(
+            merged.default_applications["text/html"],
+            ["firefox.desktop", "chromium.desktop", "epiphany.desktop"]
+        );
+        assert_eq!(merged.default_for("image/png"), Some("eog.desktop"));
+        assert_eq!(merged.added_associations["image/png"], ["eog.desktop"]);
+
+        let cache: MimeInfoCache = "[MIME Cache]\nimage/png=gimp.desktop;feh.desktop;\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            merged.associations_for("image/png", Some(&cache)),
+            ["eog.desktop", "feh.desktop"]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Searching desktop entries by name, as application launchers do.
+//!
+//! A [`SearchIndex`] holds the lowercased `Name`, `GenericName`, `Keywords` and `Comment` of a
+//! set of entries, in every language they are translated to, so that users find applications
+//! whatever their locale. Each word of a query must match the start of a word in one of
+//! these (a prefix match), or failing that appear anywhere in one (a substring match).
+
+use crate::{XdgDesktopFile, XdgDesktopValue};
+
+/// The keys searched, from most to least relevant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SearchField {
+    Name,
+    GenericName,
+    Keywords,
+    Comment,
+}
+
+impl SearchField {
+    fn of_key(key: &str) -> Option<SearchField> {
+        match key {
+            "Name" => Some(SearchField::Name),
+            "GenericName" => Some(SearchField::GenericName),
+            "Keywords" => Some(SearchField::Keywords),
+            "Comment" => Some(SearchField::Comment),
+            _ => None,
+        }
+    }
+}
+
+/// How a query matched, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MatchKind {
+    /// Every query word starts a word of the entry.
+    Prefix,
+    /// Some query word only appears inside a word of the entry.
+    Substring,
+}
+
+/// An entry matching a query, as returned by [`SearchIndex::search`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchHit<'a> {
+    /// The ID the entry was indexed under.
+    pub id: &'a str,
+    pub kind: MatchKind,
+    /// The least relevant field needed to match all of the query's words.
+    pub field: SearchField,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedEntry {
+    id: String,
+    terms: Vec<(SearchField, String)>,
+}
+
+impl IndexedEntry {
+    /// The best match for a single query word, if any.
+    fn best_match(&self, word: &str) -> Option<(MatchKind, SearchField)> {
+        self.terms
+            .iter()
+            .filter_map(|(field, term)| {
+                let kind = if term.split_whitespace().any(|w| w.starts_with(word)) {
+                    MatchKind::Prefix
+                } else if term.contains(word) {
+                    MatchKind::Substring
+                } else {
+                    return None;
+                };
+                Some((kind, *field))
+            })
+            .min()
+    }
+}
+
+/// A searchable index over a set of desktop entries.
+///
+/// ```
+/// # use xdg_desktop_parser::{search::SearchIndex, XdgDesktopFile};
+/// let files: XdgDesktopFile = "[Desktop Entry]\nName=Files\nName[de]=Dateien\n".parse().unwrap();
+/// let index = SearchIndex::new([("org.gnome.Nautilus.desktop", &files)]);
+/// assert_eq!(index.search("date")[0].id, "org.gnome.Nautilus.desktop");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    entries: Vec<IndexedEntry>,
+}
+
+impl SearchIndex {
+    /// Indexes `entries`, given as pairs of an ID (typically the desktop file ID) and the
+    /// parsed entry.
+    pub fn new<'a, S: AsRef<str>>(
+        entries: impl IntoIterator<Item = (S, &'a XdgDesktopFile)>,
+    ) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (id, file) in entries {
+            index.insert(id.as_ref(), file);
+        }
+        index
+    }
+
+    /// Adds `file` to the index under `id`.
+    pub fn insert(&mut self, id: &str, file: &XdgDesktopFile) {
+        let mut terms = Vec::new();
+        let section = file
+            .section("Desktop Entry")
+            .into_iter()
+            .flat_map(|s| s.iter());
+        for (key, value) in section {
+            let field = match SearchField::of_key(&XdgDesktopValue::strip_locale(key)) {
+                Some(f) => f,
+                None => continue,
+            };
+            let values = match value {
+                XdgDesktopValue::List(l) => l.iter().collect(),
+                v => vec![v],
+            };
+            for text in values.into_iter().filter_map(XdgDesktopValue::string_value) {
+                if !text.is_empty() {
+                    terms.push((field, text.to_lowercase()));
+                }
+            }
+        }
+        self.entries.push(IndexedEntry {
+            id: id.to_string(),
+            terms,
+        });
+    }
+
+    /// Removes every entry indexed under `id`, returning whether there was one.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        self.entries.len() < len
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Finds the entries matching every word of `query`, case-insensitively. Prefix matches
+    /// come before substring matches, then matches on more relevant fields; ties keep the
+    /// order the entries were indexed in. An empty query matches nothing.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let query = query.to_lowercase();
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<SearchHit> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let matches: Option<Vec<_>> = words.iter().map(|w| entry.best_match(w)).collect();
+                let (kind, field) = matches?.into_iter().max()?;
+                Some(SearchHit {
+                    id: &entry.id,
+                    kind,
+                    field,
+                })
+            })
+            .collect();
+        // A stable sort, so that ties keep the index order.
+        hits.sort_by_key(|hit| (hit.kind, hit.field));
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_search() {
+        let entry = |s: &str| XdgDesktopFile::from_str(&format!("[Desktop Entry]\n{}", s)).unwrap();
+        let files = entry(
+            "Name=Files\nName[de]=Dateien\nGenericName=File Manager\n\
+             Keywords=folder;explorer;\nComment=Access and organize files\n",
+        );
+        let terminal = entry("Name=Terminal\nKeywords=shell;prompt;command;\n");
+        let profiler = entry("Name=Sysprof\nComment=Profile an application or entire system\n");
+        let index = SearchIndex::new(vec![
+            ("files.desktop", &files),
+            ("terminal.desktop", &terminal),
+            ("sysprof.desktop", &profiler),
+        ]);
+        assert_eq!(index.len(), 3);
+        let ids = |query| {
+            index
+                .search(query)
+                .iter()
+                .map(|hit| hit.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("FILE"), ["files.desktop", "sysprof.desktop"]);
+        assert_eq!(ids("date"), ["files.desktop"]);
+        assert_eq!(ids("file manager"), ["files.desktop"]);
+        assert_eq!(ids("manager shell"), Vec::<&str>::new());
+        assert_eq!(ids("and"), ["files.desktop", "terminal.desktop"]);
+        assert_eq!(ids("  "), Vec::<&str>::new());
+        let mut index = index.clone();
+        assert!(index.remove("sysprof.desktop"));
+        assert!(!index.remove("sysprof.desktop"));
+        assert_eq!(index.search("FILE")[0].id, "files.desktop");
+        assert_eq!(index.search("FILE").len(), 1);
+        let hits = index.search("prom");
+        assert_eq!(hits[0].kind, MatchKind::Prefix);
+        assert_eq!(hits[0].field, SearchField::Keywords);
+        let hits = index.search("ile");
+        assert_eq!(
+            (hits[0].id, hits[0].kind, hits[0].field),
+            ("files.desktop", MatchKind::Substring, SearchField::Name)
+        );
+    }
+}
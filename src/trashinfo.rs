@@ -0,0 +1,233 @@
+//! `.trashinfo` files, which record where a trashed file came from and when it was deleted,
+//! as described by the [Trash Specification](https://specifications.freedesktop.org/trash-spec/latest/).
+
+use crate::{
+    xdg_path_bytes::{path_bytes, path_from_bytes},
+    KeyFile, XdgParseError,
+};
+use std::{fmt, fs, path::Path, path::PathBuf, str::FromStr};
+
+/// The time a file was trashed, in the `YYYY-MM-DDThh:mm:ss` form of RFC 3339 that the
+/// specification requires. The time is in the user's local time zone; an RFC 3339 offset
+/// (`Z` or `±hh:mm`) is also accepted, for files written by other tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeletionDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// The offset from UTC in minutes, if the date carried one.
+    pub offset: Option<i16>,
+}
+
+fn invalid_date(s: &str) -> XdgParseError {
+    XdgParseError::InvalidDate(s.to_string())
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl FromStr for DeletionDate {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<DeletionDate> {
+        let b = s.as_bytes();
+        let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+        // Non-ASCII text is never valid, and would make the byte offsets below unsafe to
+        // slice at.
+        let shaped = s.is_ascii()
+            && b.len() >= 19
+            && separators.iter().all(|&(i, c)| b[i] == c)
+            && matches!(b[10], b'T' | b't');
+        if !shaped {
+            return Err(invalid_date(s));
+        }
+        let field = |from: usize, to: usize| -> crate::Result<u16> {
+            let digits = &s[from..to];
+            if !digits.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(invalid_date(s));
+            }
+            digits.parse().map_err(|_| invalid_date(s))
+        };
+        let year = field(0, 4)?;
+        let [month, day, hour, minute, second] = [
+            field(5, 7)?,
+            field(8, 10)?,
+            field(11, 13)?,
+            field(14, 16)?,
+            field(17, 19)?,
+        ]
+        .map(|n| n as u8);
+        let offset = match &s[19..] {
+            "" => None,
+            "Z" | "z" => Some(0),
+            zone if zone.len() == 6 && zone.as_bytes()[3] == b':' => {
+                let sign = match zone.as_bytes()[0] {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return Err(invalid_date(s)),
+                };
+                let (h, m) = (field(20, 22)?, field(23, 25)?);
+                if h > 23 || m > 59 {
+                    return Err(invalid_date(s));
+                }
+                Some(sign * (h * 60 + m) as i16)
+            }
+            _ => return Err(invalid_date(s)),
+        };
+        let valid = (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60
+            // Allow for leap seconds.
+            && second < 61;
+        if !valid {
+            return Err(invalid_date(s));
+        }
+        Ok(DeletionDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            offset,
+        })
+    }
+}
+
+impl fmt::Display for DeletionDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        match self.offset {
+            None => Ok(()),
+            Some(0) => f.write_str("Z"),
+            Some(m) => {
+                let sign = if m < 0 { '-' } else { '+' };
+                write!(f, "{}{:02}:{:02}", sign, m.abs() / 60, m.abs() % 60)
+            }
+        }
+    }
+}
+
+/// The contents of a `.trashinfo` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashInfo {
+    /// The original location of the file: absolute, or relative to the directory containing
+    /// the trash directory.
+    pub path: PathBuf,
+    pub deletion_date: DeletionDate,
+}
+
+/// Decodes the `%XX` escapes of a URL-encoded path. Malformed escapes are kept as they are.
+fn percent_decode(s: &str) -> Vec<u8> {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let hex = b
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match (b[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// URL-encodes a path, leaving `/` and the characters RFC 3986 calls unreserved as they are.
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for &c in bytes {
+        if c.is_ascii_alphanumeric() || b"/-._~".contains(&c) {
+            out.push(c as char);
+        } else {
+            out.push_str(&format!("%{:02X}", c));
+        }
+    }
+    out
+}
+
+impl TrashInfo {
+    pub fn new(path: impl Into<PathBuf>, deletion_date: DeletionDate) -> TrashInfo {
+        TrashInfo {
+            path: path.into(),
+            deletion_date,
+        }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<TrashInfo> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+impl FromStr for TrashInfo {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<TrashInfo> {
+        let file = KeyFile::from_str(s)?;
+        let key = |key: &str| {
+            file.raw_value("Trash Info", key)
+                .ok_or_else(|| XdgParseError::MissingKey(format!("Trash Info/{}", key)))
+        };
+        Ok(TrashInfo {
+            path: path_from_bytes(percent_decode(key("Path")?)),
+            deletion_date: key("DeletionDate")?.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for TrashInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Trash Info]")?;
+        writeln!(f, "Path={}", percent_encode(&path_bytes(&self.path)))?;
+        writeln!(f, "DeletionDate={}", self.deletion_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let input = "[Trash Info]\nPath=/home/user/My%20Files/r%C3%A9sum%C3%A9.txt\n\
+                     DeletionDate=2004-08-31T22:32:08\n";
+        let info = TrashInfo::from_str(input).unwrap();
+        assert_eq!(info.path, Path::new("/home/user/My Files/résumé.txt"));
+        assert_eq!(
+            (info.deletion_date.month, info.deletion_date.second),
+            (8, 8)
+        );
+        assert_eq!(info.to_string(), input);
+
+        let date: DeletionDate = "2020-02-29T00:00:00+05:30".parse().unwrap();
+        assert_eq!(date.offset, Some(330));
+        assert_eq!(date.to_string(), "2020-02-29T00:00:00+05:30");
+        assert!("2021-02-29T00:00:00".parse::<DeletionDate>().is_err());
+        assert!("2021-01-01 00:00:00".parse::<DeletionDate>().is_err());
+        assert!("2021-01-01T00:00:0\u{e9}".parse::<DeletionDate>().is_err());
+        assert!(TrashInfo::from_str("[Trash Info]\nPath=/tmp/a\n").is_err());
+    }
+}
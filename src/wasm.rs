@@ -0,0 +1,122 @@
+//! Bindings for JavaScript, for checking desktop entries in a browser.
+//!
+//! Built for `wasm32-unknown-unknown` with the `wasm` feature, the crate exports `parse`,
+//! which returns a file's sections as an object of objects, and `validate`, which returns a
+//! [`Validation`]. Both take the file's contents as a string; nothing touches the file
+//! system.
+
+use crate::XdgDesktopFile;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file breaks the specification, or couldn't be parsed at all.
+    Error,
+    /// The file is usable, but something in it is likely a mistake.
+    Warning,
+}
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The section and key at fault, when the problem is a value that failed to parse.
+    pub section: Option<String>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+/// The outcome of [`validate`]. The file is valid if none of the diagnostics are errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Validation {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, message: impl ToString) -> Diagnostic {
+        Diagnostic {
+            severity,
+            section: None,
+            key: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Checks `contents`: errors are values that failed to parse and violations found by
+/// [`XdgDesktopFile::validate`]; warnings are the findings of [`XdgDesktopFile::report`].
+pub fn validate(contents: &str) -> Validation {
+    let file = match contents.parse::<XdgDesktopFile>() {
+        Ok(file) => file,
+        Err(e) => {
+            return Validation {
+                valid: false,
+                diagnostics: vec![Diagnostic::new(Severity::Error, e)],
+            }
+        }
+    };
+    let mut diagnostics: Vec<Diagnostic> = file
+        .errors()
+        .map(|(section, key, e)| Diagnostic {
+            section: Some(section.to_string()),
+            key: Some(key.to_string()),
+            ..Diagnostic::new(Severity::Error, e)
+        })
+        .collect();
+    diagnostics.extend(
+        file.validate()
+            .iter()
+            .map(|w| Diagnostic::new(Severity::Error, w)),
+    );
+    let valid = diagnostics.is_empty();
+    diagnostics.extend(
+        file.report()
+            .iter()
+            .map(|w| Diagnostic::new(Severity::Warning, w)),
+    );
+    Validation { valid, diagnostics }
+}
+
+#[wasm_bindgen(js_name = parse)]
+pub fn parse_js(contents: &str) -> Result<JsValue, JsError> {
+    let file: XdgDesktopFile = contents.parse()?;
+    Ok(serde_wasm_bindgen::to_value(&file)?)
+}
+
+#[wasm_bindgen(js_name = validate)]
+pub fn validate_js(contents: &str) -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(&validate(contents))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let result = validate("[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\n");
+        assert_eq!(
+            result,
+            Validation {
+                valid: true,
+                diagnostics: Vec::new()
+            }
+        );
+        let result = validate("[Desktop Entry]\nType=Application\nExec=foo\nTerminal=maybe\n");
+        assert!(!result.valid);
+        let errors: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].key.as_deref(), Some("Terminal"));
+        assert_eq!(errors[1].key, None);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["diagnostics"][0]["severity"], "error");
+        assert!(!validate("Name=Foo\n").valid);
+    }
+}
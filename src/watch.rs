@@ -0,0 +1,304 @@
+//! Monitoring of the installed desktop entries for changes.
+//!
+//! A [`DesktopDatabaseWatcher`] watches the same directories as a
+//! [`Discovery`](crate::discovery::Discovery), using inotify, and reports each desktop file
+//! that is created, modified or removed as a [`WatchEvent`] carrying the re-parsed entry.
+//! Shadowing is taken into account: a change to a file that another directory overrides is
+//! not reported, and removing a file that shadowed another reports a change to the one that
+//! takes its place.
+
+use crate::{
+    discovery::{desktop_file_id, Discovery},
+    XdgDesktopFile,
+};
+use indexmap::IndexMap;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::{
+    collections::HashMap,
+    fs, io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+/// A change to the set of installed desktop entries.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// An entry with a new desktop file ID appeared.
+    Added {
+        id: String,
+        path: PathBuf,
+        entry: crate::Result<XdgDesktopFile>,
+    },
+    /// The file providing an entry was modified or replaced. `path` may differ from the
+    /// entry's previous path, e.g. when the file that shadowed it was removed.
+    Changed {
+        id: String,
+        path: PathBuf,
+        entry: crate::Result<XdgDesktopFile>,
+    },
+    /// No file provides the entry any more.
+    Removed { id: String, path: PathBuf },
+}
+
+const MASK: WatchMask = WatchMask::CREATE
+    .union(WatchMask::CLOSE_WRITE)
+    .union(WatchMask::DELETE)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::MOVED_TO);
+
+const BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug)]
+pub struct DesktopDatabaseWatcher {
+    discovery: Discovery,
+    inotify: Inotify,
+    /// The directory each watch is on, and the search directory it is below.
+    watches: HashMap<WatchDescriptor, (PathBuf, PathBuf)>,
+    /// The file currently providing each ID.
+    known: IndexMap<String, PathBuf>,
+    buffer: Vec<u8>,
+}
+
+impl DesktopDatabaseWatcher {
+    /// Watches the directories of [`Discovery::new`].
+    pub fn new() -> io::Result<DesktopDatabaseWatcher> {
+        DesktopDatabaseWatcher::with_discovery(Discovery::new())
+    }
+
+    /// Watches the directories searched by `discovery`. Directories that don't exist yet are
+    /// not watched.
+    pub fn with_discovery(discovery: Discovery) -> io::Result<DesktopDatabaseWatcher> {
+        let mut watcher = DesktopDatabaseWatcher {
+            known: discovery.paths(),
+            discovery,
+            inotify: Inotify::init()?,
+            watches: HashMap::new(),
+            buffer: vec![0; BUFFER_SIZE],
+        };
+        for root in watcher.discovery.dirs().to_vec() {
+            if root.is_dir() {
+                watcher.watch_tree(&root, &root, &mut Vec::new())?;
+            }
+        }
+        Ok(watcher)
+    }
+
+    /// The ID of every entry currently installed, with the file that provides it.
+    pub fn paths(&self) -> &IndexMap<String, PathBuf> {
+        &self.known
+    }
+
+    fn watch_tree(
+        &mut self,
+        root: &Path,
+        dir: &Path,
+        ancestors: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        // Unreadable directories and symbolic links to a directory above are skipped, as
+        // `Discovery` skips them.
+        let Ok(canonical) = fs::canonicalize(dir) else {
+            return Ok(());
+        };
+        if ancestors.contains(&canonical) {
+            return Ok(());
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(());
+        };
+        let wd = self.inotify.watches().add(dir, MASK)?;
+        self.watches
+            .insert(wd, (dir.to_path_buf(), root.to_path_buf()));
+        ancestors.push(canonical);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.watch_tree(root, &path, ancestors)?;
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+
+    /// Waits until at least one file changes, then returns the resulting events. The list
+    /// may be empty if none of the changes affected the installed entries.
+    pub fn wait(&mut self) -> io::Result<Vec<WatchEvent>> {
+        self.read(true)
+    }
+
+    /// Returns the events for the changes that have happened since the last call, without
+    /// blocking. Use the [raw file descriptor](AsRawFd) to integrate with an event loop.
+    pub fn poll(&mut self) -> io::Result<Vec<WatchEvent>> {
+        self.read(false)
+    }
+
+    fn read(&mut self, blocking: bool) -> io::Result<Vec<WatchEvent>> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        let events = if blocking {
+            self.inotify.read_events_blocking(&mut buffer)
+        } else {
+            self.inotify.read_events(&mut buffer)
+        };
+        let events: Vec<_> = match events {
+            Ok(events) => events.map(|e| e.to_owned()).collect(),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Vec::new(),
+            Err(e) => {
+                self.buffer = buffer;
+                return Err(e);
+            }
+        };
+        self.buffer = buffer;
+        // A single write produces several events; look at each ID once.
+        let mut ids: Vec<String> = Vec::new();
+        let mut rescan = false;
+        for event in events {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                rescan = true;
+                continue;
+            }
+            if event.mask.contains(EventMask::IGNORED) {
+                self.watches.remove(&event.wd);
+                continue;
+            }
+            let (path, root) = match (self.watches.get(&event.wd), &event.name) {
+                (Some((dir, root)), Some(name)) => (dir.join(name), root.clone()),
+                _ => continue,
+            };
+            if event.mask.contains(EventMask::ISDIR) {
+                if event
+                    .mask
+                    .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+                {
+                    // Files may have been created before the watch was; the rescan picks
+                    // them up.
+                    let _ = self.watch_tree(&root, &path, &mut Vec::new());
+                }
+                rescan = true;
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "desktop") {
+                continue;
+            }
+            if let Some(id) = desktop_file_id(&root, &path) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        if rescan {
+            let current = self.discovery.paths();
+            let known = &self.known;
+            let changed = known
+                .keys()
+                .filter(|id| !current.contains_key(*id))
+                .chain(
+                    current
+                        .iter()
+                        .filter_map(|(id, path)| (known.get(id) != Some(path)).then_some(id)),
+                )
+                .cloned()
+                .collect::<Vec<_>>();
+            for id in changed {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        for id in ids {
+            self.update(id, &mut out);
+        }
+        Ok(out)
+    }
+
+    /// Re-resolves `id` and records what changed.
+    fn update(&mut self, id: String, out: &mut Vec<WatchEvent>) {
+        match (self.discovery.find(&id), self.known.get(&id)) {
+            (Some(path), previous) => {
+                let entry = XdgDesktopFile::from_path(&path);
+                let event = if previous.is_some() {
+                    WatchEvent::Changed {
+                        id: id.clone(),
+                        path: path.clone(),
+                        entry,
+                    }
+                } else {
+                    WatchEvent::Added {
+                        id: id.clone(),
+                        path: path.clone(),
+                        entry,
+                    }
+                };
+                self.known.insert(id, path);
+                out.push(event);
+            }
+            (None, Some(_)) => {
+                if let Some(path) = self.known.shift_remove(&id) {
+                    out.push(WatchEvent::Removed { id, path });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+impl AsRawFd for DesktopDatabaseWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (user, system) = (root.join("user"), root.join("system"));
+        fs::create_dir_all(&user).unwrap();
+        fs::create_dir_all(&system).unwrap();
+        std::os::unix::fs::symlink(&user, user.join("loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing"), system.join("dangling")).unwrap();
+        let entry = |name: &str| format!("[Desktop Entry]\nType=Application\nName={}\n", name);
+        fs::write(system.join("htop.desktop"), entry("htop")).unwrap();
+        let discovery = Discovery::with_dirs(vec![&user, &system]);
+        let mut watcher = DesktopDatabaseWatcher::with_discovery(discovery).unwrap();
+        assert_eq!(watcher.watches.len(), 2);
+        let missing = root.join("missing");
+        assert!(watcher
+            .watch_tree(&system, &missing, &mut Vec::new())
+            .is_ok());
+        assert_eq!(watcher.watches.len(), 2);
+
+        fs::write(system.join("foo.desktop"), entry("Foo")).unwrap();
+        fs::write(user.join("htop.desktop"), entry("User htop")).unwrap();
+        fs::write(user.join("notes.txt"), "").unwrap();
+        let added = watcher.poll().unwrap();
+        fs::remove_file(user.join("htop.desktop")).unwrap();
+        let removed = watcher.poll().unwrap();
+
+        let name = |entry: &crate::Result<XdgDesktopFile>| {
+            let entry = entry.as_ref().unwrap();
+            entry.get_str("Desktop Entry", "Name").unwrap().to_string()
+        };
+        match &added[..] {
+            [WatchEvent::Added { id, entry: foo, .. }, WatchEvent::Changed {
+                path, entry: htop, ..
+            }] => {
+                assert_eq!(id, "foo.desktop");
+                assert_eq!(name(foo), "Foo");
+                assert_eq!(path, &user.join("htop.desktop"));
+                assert_eq!(name(htop), "User htop");
+            }
+            other => panic!("unexpected events {:?}", other),
+        }
+        match &removed[..] {
+            [WatchEvent::Changed { path, entry, .. }] => {
+                assert_eq!(path, &system.join("htop.desktop"));
+                assert_eq!(name(entry), "htop");
+            }
+            other => panic!("unexpected events {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Lookup of the XDG Base Directory Specification's search paths.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Reads a path from `var`, ignoring it if it's unset, empty or relative, as the
+/// specification requires.
+fn env_path(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+fn env_paths(var: &str, default: &[&str]) -> Vec<PathBuf> {
+    let paths: Vec<PathBuf> = env::var_os(var)
+        .map(|v| env::split_paths(&v).filter(|p| p.is_absolute()).collect())
+        .unwrap_or_default();
+    if paths.is_empty() {
+        default.iter().map(PathBuf::from).collect()
+    } else {
+        paths
+    }
+}
+
+pub(crate) fn home() -> Option<PathBuf> {
+    env_path("HOME")
+}
+
+fn home_relative(var: &str, fallback: &str) -> Option<PathBuf> {
+    env_path(var).or_else(|| home().map(|h| h.join(fallback)))
+}
+
+pub(crate) fn data_home() -> Option<PathBuf> {
+    home_relative("XDG_DATA_HOME", ".local/share")
+}
+
+pub(crate) fn data_dirs() -> Vec<PathBuf> {
+    env_paths("XDG_DATA_DIRS", &["/usr/local/share", "/usr/share"])
+}
+
+pub(crate) fn config_home() -> Option<PathBuf> {
+    home_relative("XDG_CONFIG_HOME", ".config")
+}
+
+pub(crate) fn config_dirs() -> Vec<PathBuf> {
+    env_paths("XDG_CONFIG_DIRS", &["/etc/xdg"])
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, each joined with `subdir`, in order of
+/// decreasing precedence.
+pub(crate) fn data_paths(subdir: impl AsRef<Path>) -> Vec<PathBuf> {
+    data_home()
+        .into_iter()
+        .chain(data_dirs())
+        .map(|p| p.join(subdir.as_ref()))
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME` followed by `$XDG_CONFIG_DIRS`, each joined with `subdir`, in order of
+/// decreasing precedence.
+pub(crate) fn config_paths(subdir: impl AsRef<Path>) -> Vec<PathBuf> {
+    config_home()
+        .into_iter()
+        .chain(config_dirs())
+        .map(|p| p.join(subdir.as_ref()))
+        .collect()
+}
+
+/// The desktop environments named in `$XDG_CURRENT_DESKTOP`, in order.
+pub(crate) fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|v| {
+            v.split(':')
+                .filter(|d| !d.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,384 @@
+//! An on-disk cache of parsed desktop entries, for launchers that need the full list of
+//! entries quickly at startup.
+//!
+//! The cache is a single binary file mapping each file's path to its modification time,
+//! size and parsed contents. Only the contents of files that parsed cleanly, without warnings
+//! or values that failed to parse, are stored; the rest are parsed every time so that their
+//! diagnostics are reported as usual, but are still recorded, without contents, so that they
+//! don't cause the cache to be rewritten on every load.
+
+use crate::{
+    discovery::{parse_all, Discovery},
+    xdg_desktop_section::XdgDesktopSection,
+    xdg_desktop_value::XdgDesktopValue,
+    xdg_path_bytes::{path_bytes, path_from_bytes},
+    xdg_section_name::SectionName,
+    XdgDesktopFile,
+};
+use indexmap::IndexMap;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const MAGIC: &[u8; 8] = b"XDGCACHE";
+/// Bumped whenever the layout changes, invalidating existing caches.
+const VERSION: u32 = 3;
+
+/// What a file looked like when it was parsed. A file whose stamp differs is re-parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    secs: u64,
+    nanos: u32,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Stamp> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Stamp {
+            secs: mtime.as_secs(),
+            nanos: mtime.subsec_nanos(),
+            len: metadata.len(),
+        })
+    }
+}
+
+fn is_cacheable(file: &XdgDesktopFile) -> bool {
+    file.warnings().is_empty() && file.errors().next().is_none()
+}
+
+fn put_u32(out: &mut Vec<u8>, n: u32) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, n: u64) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+fn put_value(out: &mut Vec<u8>, value: &XdgDesktopValue) {
+    match value {
+        XdgDesktopValue::String(s) => {
+            out.push(0);
+            put_bytes(out, s.as_bytes());
+        }
+        XdgDesktopValue::LocaleString(s) => {
+            out.push(1);
+            put_bytes(out, s.as_bytes());
+        }
+        XdgDesktopValue::IconString(s) => {
+            out.push(2);
+            put_bytes(out, s.as_bytes());
+        }
+        XdgDesktopValue::Bool(b) => out.extend_from_slice(&[3, *b as u8]),
+        XdgDesktopValue::Numeric(n) => {
+            out.push(4);
+            put_u64(out, n.to_bits());
+        }
+        XdgDesktopValue::Integer(n) => {
+            out.push(5);
+            put_u64(out, *n as u64);
+        }
+        XdgDesktopValue::List(l) => {
+            out.push(6);
+            put_u32(out, l.len() as u32);
+            for v in l {
+                put_value(out, v);
+            }
+        }
+    }
+}
+
+fn put_file(out: &mut Vec<u8>, file: &XdgDesktopFile) {
+    put_u32(out, file.sections().count() as u32);
+    for (name, section) in file.sections() {
+        put_bytes(out, name.as_str().as_bytes());
+        put_u32(out, section.len() as u32);
+        for (key, value) in section {
+            put_bytes(out, key.as_bytes());
+            put_bytes(out, section.raw_value(key).unwrap_or_default().as_bytes());
+            put_value(out, value);
+        }
+    }
+}
+
+/// Reads the fields written by the `put_` functions. Every method returns `None` once the
+/// input is exhausted or malformed, at which point the whole cache is discarded.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.bytes.len() {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.bytes()?).ok()
+    }
+
+    fn value(&mut self) -> Option<XdgDesktopValue> {
+        Some(match self.u8()? {
+            0 => XdgDesktopValue::String(self.str()?.to_string()),
+            1 => XdgDesktopValue::LocaleString(self.str()?.to_string()),
+            2 => XdgDesktopValue::IconString(self.str()?.to_string()),
+            3 => XdgDesktopValue::Bool(self.u8()? != 0),
+            4 => XdgDesktopValue::Numeric(f64::from_bits(self.u64()?)),
+            5 => XdgDesktopValue::Integer(self.u64()? as i64),
+            6 => {
+                let len = self.u32()?;
+                let values: Option<Vec<_>> = (0..len).map(|_| self.value()).collect();
+                XdgDesktopValue::List(values?)
+            }
+            _ => return None,
+        })
+    }
+
+    fn file(&mut self) -> Option<XdgDesktopFile> {
+        let count = self.u32()?;
+        let mut sections = IndexMap::new();
+        for _ in 0..count {
+            let name = SectionName::new(self.str()?).ok()?;
+            let mut section = XdgDesktopSection::new();
+            for _ in 0..self.u32()? {
+                let key = self.str()?;
+                let raw = self.str()?;
+                section.insert_parsed(key, Ok(self.value()?), Some(raw));
+            }
+            sections.insert(name, section);
+        }
+        Some(XdgDesktopFile::from_sections(sections))
+    }
+}
+
+/// The cached entries by path, with `None` for files that are recorded but not cached.
+type Entries = HashMap<PathBuf, (Stamp, Option<XdgDesktopFile>)>;
+
+fn decode(bytes: &[u8]) -> Option<Entries> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != VERSION {
+        return None;
+    }
+    let mut out = HashMap::new();
+    while !reader.bytes.is_empty() {
+        let path = path_from_bytes(reader.bytes()?.to_vec());
+        let stamp = Stamp {
+            secs: reader.u64()?,
+            nanos: reader.u32()?,
+            len: reader.u64()?,
+        };
+        let file = match reader.u8()? {
+            0 => None,
+            1 => Some(reader.file()?),
+            _ => return None,
+        };
+        out.insert(path, (stamp, file));
+    }
+    Some(out)
+}
+
+fn encode<'a>(
+    entries: impl Iterator<Item = (&'a Path, Stamp, Option<&'a XdgDesktopFile>)>,
+) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    put_u32(&mut out, VERSION);
+    for (path, stamp, file) in entries {
+        put_bytes(&mut out, &path_bytes(path));
+        put_u64(&mut out, stamp.secs);
+        put_u32(&mut out, stamp.nanos);
+        put_u64(&mut out, stamp.len);
+        match file {
+            Some(file) => {
+                out.push(1);
+                put_file(&mut out, file);
+            }
+            None => out.push(0),
+        }
+    }
+    out
+}
+
+/// Replaces the file at `path` without leaving a partly written cache behind if interrupted.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+impl Discovery {
+    /// Like [`load`](Discovery::load), but reuses the entries stored in the cache file at
+    /// `cache` for files whose modification time and size haven't changed, parsing only the
+    /// rest. The cache is then rewritten if the files changed. A missing, outdated or
+    /// corrupt cache is ignored, and failing to write it isn't an error, since the entries
+    /// can always be parsed again.
+    pub fn load_cached(
+        &self,
+        cache: impl AsRef<Path>,
+    ) -> IndexMap<String, crate::Result<XdgDesktopFile>> {
+        let cache = cache.as_ref();
+        let mut cached = fs::read(cache)
+            .ok()
+            .and_then(|bytes| decode(&bytes))
+            .unwrap_or_default();
+        let paths = self.paths();
+        // Taken before parsing, so that a file modified meanwhile is re-parsed next time.
+        let stamps: HashMap<&Path, Stamp> = paths
+            .values()
+            .filter_map(|p| Some((p.as_path(), Stamp::of(p)?)))
+            .collect();
+        let mut hits = HashMap::new();
+        let mut stale = IndexMap::new();
+        let mut updated = false;
+        for (id, path) in &paths {
+            let stamp = stamps.get(path.as_path());
+            match cached.remove(path) {
+                Some((cached_stamp, file)) if stamp == Some(&cached_stamp) => match file {
+                    Some(file) => {
+                        hits.insert(id.clone(), file);
+                    }
+                    None => {
+                        stale.insert(id.clone(), path.clone());
+                    }
+                },
+                old => {
+                    // A file without a stamp can't be recorded, so it alone doesn't count.
+                    updated |= old.is_some() || stamp.is_some();
+                    stale.insert(id.clone(), path.clone());
+                }
+            }
+        }
+        updated |= !cached.is_empty();
+        let mut parsed = parse_all(stale);
+        let out: IndexMap<String, crate::Result<XdgDesktopFile>> = paths
+            .keys()
+            .filter_map(|id| {
+                let entry = match hits.remove(id) {
+                    Some(file) => Ok(file),
+                    None => parsed.shift_remove(id)?,
+                };
+                Some((id.clone(), entry))
+            })
+            .collect();
+        if updated {
+            let entries = out.iter().filter_map(|(id, entry)| {
+                let path = paths[id].as_path();
+                let file = entry.as_ref().ok().filter(|f| is_cacheable(f));
+                Some((path, *stamps.get(path)?, file))
+            });
+            let _ = write_atomic(cache, &encode(entries));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_load_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let apps = root.join("applications");
+        let cache = root.join("cache/entries.bin");
+        fs::create_dir_all(&apps).unwrap();
+        let entry = |name: &str| {
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\nTerminal=true\n\
+                 Categories=System;Monitor;\nX-Weight=2.5\n",
+                name
+            )
+        };
+        fs::write(apps.join("htop.desktop"), entry("htop")).unwrap();
+        fs::write(apps.join("foo.desktop"), entry("Foo")).unwrap();
+        fs::write(apps.join("broken.desktop"), "Name=Broken\n").unwrap();
+        let discovery = Discovery::with_dirs([&apps]);
+
+        let first = discovery.load_cached(&cache);
+        let written = fs::read(&cache).unwrap();
+        let past = UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&cache)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        let second = discovery.load_cached(&cache);
+        let unchanged = fs::metadata(&cache).unwrap().modified().unwrap() == past;
+        fs::write(apps.join("foo.desktop"), entry("Changed Foo")).unwrap();
+        fs::remove_file(apps.join("htop.desktop")).unwrap();
+        let third = discovery.load_cached(&cache);
+        let remaining = decode(&fs::read(&cache).unwrap()).unwrap();
+        fs::write(&cache, b"garbage").unwrap();
+        let fourth = discovery.load_cached(&cache);
+
+        let names = |loaded: &IndexMap<String, crate::Result<XdgDesktopFile>>| {
+            loaded
+                .iter()
+                .map(|(id, e)| {
+                    let name = e.as_ref().ok().map(|f| f.get_str("Desktop Entry", "Name"));
+                    (id.clone(), name.flatten().map(str::to_string))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&first), names(&second));
+        assert!(unchanged);
+        let recorded = decode(&written).unwrap();
+        assert_eq!(recorded.len(), 3);
+        assert!(recorded[&apps.join("broken.desktop")].1.is_none());
+        let htop = second["htop.desktop"].as_ref().unwrap();
+        let parsed = XdgDesktopFile::from_str(&entry("htop")).unwrap();
+        assert_eq!(htop.to_string(), parsed.to_string());
+        assert_eq!(htop.get_bool("Desktop Entry", "Terminal"), Some(true));
+        assert!(second["broken.desktop"].is_err());
+        let third = names(&third);
+        assert_eq!(
+            third,
+            [
+                ("broken.desktop".to_string(), None),
+                ("foo.desktop".to_string(), Some("Changed Foo".to_string())),
+            ]
+        );
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[&apps.join("foo.desktop")].1.is_some());
+        assert_eq!(names(&fourth), third);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::XdgDesktopValue};
+use std::{convert::Infallible, fmt, str::FromStr};
+
+macro_rules! categories {
+    (
+        main: { $($main:ident = $main_name:literal,)* }
+        additional: { $($additional:ident = $additional_name:literal,)* }
+        reserved: { $($reserved:ident = $reserved_name:literal,)* }
+    ) => {
+        /// A category registered by the Desktop Menu Specification, as listed in an entry's
+        /// `Categories` key.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum Category {
+            $($main,)*
+            $($additional,)*
+            $($reserved,)*
+            /// An unregistered category, e.g. a vendor's `X-` category.
+            Other(String),
+        }
+
+        impl Category {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Category::$main => $main_name,)*
+                    $(Category::$additional => $additional_name,)*
+                    $(Category::$reserved => $reserved_name,)*
+                    Category::Other(s) => s,
+                }
+            }
+
+            /// Whether this is one of the main categories, which menus are built from. Every
+            /// application should list at least one.
+            pub fn is_main(&self) -> bool {
+                matches!(self, $(Category::$main)|*)
+            }
+
+            /// Whether this is one of the reserved categories, which are only meaningful
+            /// together with `OnlyShowIn`.
+            pub fn is_reserved(&self) -> bool {
+                matches!(self, $(Category::$reserved)|*)
+            }
+        }
+
+        impl From<&str> for Category {
+            fn from(s: &str) -> Category {
+                match s {
+                    $($main_name => Category::$main,)*
+                    $($additional_name => Category::$additional,)*
+                    $($reserved_name => Category::$reserved,)*
+                    other => Category::Other(other.to_string()),
+                }
+            }
+        }
+    };
+}
+
+categories! {
+    main: {
+        AudioVideo = "AudioVideo",
+        Audio = "Audio",
+        Video = "Video",
+        Development = "Development",
+        Education = "Education",
+        Game = "Game",
+        Graphics = "Graphics",
+        Network = "Network",
+        Office = "Office",
+        Science = "Science",
+        Settings = "Settings",
+        System = "System",
+        Utility = "Utility",
+    }
+    additional: {
+        Building = "Building",
+        Debugger = "Debugger",
+        IDE = "IDE",
+        GUIDesigner = "GUIDesigner",
+        Profiling = "Profiling",
+        RevisionControl = "RevisionControl",
+        Translation = "Translation",
+        Calendar = "Calendar",
+        ContactManagement = "ContactManagement",
+        Database = "Database",
+        Dictionary = "Dictionary",
+        Chart = "Chart",
+        Email = "Email",
+        Finance = "Finance",
+        FlowChart = "FlowChart",
+        PDA = "PDA",
+        ProjectManagement = "ProjectManagement",
+        Presentation = "Presentation",
+        Spreadsheet = "Spreadsheet",
+        WordProcessor = "WordProcessor",
+        Graphics2D = "2DGraphics",
+        VectorGraphics = "VectorGraphics",
+        RasterGraphics = "RasterGraphics",
+        Graphics3D = "3DGraphics",
+        Scanning = "Scanning",
+        OCR = "OCR",
+        Photography = "Photography",
+        Publishing = "Publishing",
+        Viewer = "Viewer",
+        TextTools = "TextTools",
+        DesktopSettings = "DesktopSettings",
+        HardwareSettings = "HardwareSettings",
+        Printing = "Printing",
+        PackageManager = "PackageManager",
+        Dialup = "Dialup",
+        InstantMessaging = "InstantMessaging",
+        Chat = "Chat",
+        IRCClient = "IRCClient",
+        Feed = "Feed",
+        FileTransfer = "FileTransfer",
+        HamRadio = "HamRadio",
+        News = "News",
+        P2P = "P2P",
+        RemoteAccess = "RemoteAccess",
+        Telephony = "Telephony",
+        TelephonyTools = "TelephonyTools",
+        VideoConference = "VideoConference",
+        WebBrowser = "WebBrowser",
+        WebDevelopment = "WebDevelopment",
+        Midi = "Midi",
+        Mixer = "Mixer",
+        Sequencer = "Sequencer",
+        Tuner = "Tuner",
+        TV = "TV",
+        AudioVideoEditing = "AudioVideoEditing",
+        Player = "Player",
+        Recorder = "Recorder",
+        DiscBurning = "DiscBurning",
+        ActionGame = "ActionGame",
+        AdventureGame = "AdventureGame",
+        ArcadeGame = "ArcadeGame",
+        BoardGame = "BoardGame",
+        BlocksGame = "BlocksGame",
+        CardGame = "CardGame",
+        KidsGame = "KidsGame",
+        LogicGame = "LogicGame",
+        RolePlaying = "RolePlaying",
+        Shooter = "Shooter",
+        Simulation = "Simulation",
+        SportsGame = "SportsGame",
+        StrategyGame = "StrategyGame",
+        Art = "Art",
+        Construction = "Construction",
+        Music = "Music",
+        Languages = "Languages",
+        ArtificialIntelligence = "ArtificialIntelligence",
+        Astronomy = "Astronomy",
+        Biology = "Biology",
+        Chemistry = "Chemistry",
+        ComputerScience = "ComputerScience",
+        DataVisualization = "DataVisualization",
+        Economy = "Economy",
+        Electricity = "Electricity",
+        Geography = "Geography",
+        Geology = "Geology",
+        Geoscience = "Geoscience",
+        History = "History",
+        Humanities = "Humanities",
+        ImageProcessing = "ImageProcessing",
+        Literature = "Literature",
+        Maps = "Maps",
+        Math = "Math",
+        NumericalAnalysis = "NumericalAnalysis",
+        MedicalSoftware = "MedicalSoftware",
+        Physics = "Physics",
+        Robotics = "Robotics",
+        Spirituality = "Spirituality",
+        Sports = "Sports",
+        ParallelComputing = "ParallelComputing",
+        Amusement = "Amusement",
+        Archiving = "Archiving",
+        Compression = "Compression",
+        Electronics = "Electronics",
+        Emulator = "Emulator",
+        Engineering = "Engineering",
+        FileTools = "FileTools",
+        FileManager = "FileManager",
+        TerminalEmulator = "TerminalEmulator",
+        Filesystem = "Filesystem",
+        Monitor = "Monitor",
+        Security = "Security",
+        Accessibility = "Accessibility",
+        Calculator = "Calculator",
+        Clock = "Clock",
+        TextEditor = "TextEditor",
+        Documentation = "Documentation",
+        Adult = "Adult",
+        Core = "Core",
+        KDE = "KDE",
+        GNOME = "GNOME",
+        XFCE = "XFCE",
+        DDE = "DDE",
+        GTK = "GTK",
+        Qt = "Qt",
+        Motif = "Motif",
+        Java = "Java",
+        ConsoleOnly = "ConsoleOnly",
+    }
+    reserved: {
+        Screensaver = "Screensaver",
+        TrayIcon = "TrayIcon",
+        Applet = "Applet",
+        Shell = "Shell",
+    }
+}
+
+impl FromStr for Category {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Category, Infallible> {
+        Ok(Category::from(s))
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl XdgDesktopFile {
+    /// The entry's `Categories`, in the order listed.
+    pub fn categories(&self) -> Vec<Category> {
+        match self.get("Desktop Entry", "Categories") {
+            Some(XdgDesktopValue::List(l)) => l
+                .iter()
+                .filter_map(XdgDesktopValue::string_value)
+                .filter(|c| !c.is_empty())
+                .map(Category::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nCategories=System;TerminalEmulator;2DGraphics;X-Vendor;\n",
+        )
+        .unwrap();
+        let categories = file.categories();
+        assert_eq!(
+            categories,
+            [
+                Category::System,
+                Category::TerminalEmulator,
+                Category::Graphics2D,
+                Category::Other("X-Vendor".into()),
+            ]
+        );
+        assert!(categories[0].is_main());
+        assert!(!categories[1].is_main());
+        assert_eq!(categories[2].to_string(), "2DGraphics");
+    }
+}
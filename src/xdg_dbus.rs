@@ -0,0 +1,155 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile,
+    xdg_desktop_value::XdgDesktopValue,
+    xdg_launch::{new_startup_id, LaunchOptions, Launched},
+};
+use std::{collections::HashMap, io, path};
+use zbus::{blocking::Connection, zvariant::Value};
+
+/// How [`XdgDesktopFile::activate`] started an application.
+#[derive(Debug)]
+pub enum Activation {
+    /// Through the `org.freedesktop.Application` D-Bus interface. The startup notification
+    /// ID passed to the application is included.
+    DBus { startup_id: String },
+    /// By running its `Exec` command.
+    Spawned(Launched),
+}
+
+/// The application's well-known bus name: its desktop file ID without the `.desktop` suffix.
+fn bus_name(desktop_file_id: &str) -> &str {
+    desktop_file_id
+        .strip_suffix(".desktop")
+        .unwrap_or(desktop_file_id)
+}
+
+/// The object path the application exports its interface at, derived from its bus name.
+fn object_path(bus_name: &str) -> String {
+    format!("/{}", bus_name.replace('.', "/").replace('-', "_"))
+}
+
+/// Converts a path to a `file://` URI, leaving anything that already looks like a URI alone.
+/// Relative paths are taken relative to the current directory.
+fn file_uri(file: &str) -> io::Result<String> {
+    if file.contains("://") {
+        return Ok(file.to_string());
+    }
+    let path = path::absolute(file)?;
+    let mut out = String::from("file://");
+    for &b in path.as_os_str().as_encoded_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            b => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    Ok(out)
+}
+
+fn call_application(
+    desktop_file_id: &str,
+    action: Option<&str>,
+    files: &[&str],
+    platform_data: HashMap<&str, Value<'_>>,
+) -> zbus::Result<()> {
+    let name = bus_name(desktop_file_id);
+    let path = object_path(name);
+    let connection = Connection::session()?;
+    let (path, interface) = (path.as_str(), Some("org.freedesktop.Application"));
+    match action {
+        Some(a) => connection.call_method(
+            Some(name),
+            path,
+            interface,
+            "ActivateAction",
+            &(a, Vec::<Value>::new(), platform_data),
+        ),
+        None if files.is_empty() => {
+            connection.call_method(Some(name), path, interface, "Activate", &(platform_data,))
+        }
+        None => {
+            let uris = files
+                .iter()
+                .map(|f| file_uri(f))
+                .collect::<io::Result<Vec<_>>>()?;
+            connection.call_method(Some(name), path, interface, "Open", &(uris, platform_data))
+        }
+    }?;
+    Ok(())
+}
+
+impl XdgDesktopFile {
+    /// Starts the application, or one of its actions, the way the specification prefers:
+    /// entries with `DBusActivatable=true` are activated through the
+    /// `org.freedesktop.Application` interface at the bus name derived from
+    /// `desktop_file_id`; files are passed to its `Open` method as URIs. If that fails, or the
+    /// entry isn't D-Bus activatable, the application is [spawned](XdgDesktopFile::spawn)
+    /// from `Exec` instead.
+    pub fn activate(
+        &self,
+        desktop_file_id: &str,
+        action: Option<&str>,
+        files: &[&str],
+        options: &LaunchOptions,
+    ) -> crate::Result<Activation> {
+        if let Some(XdgDesktopValue::Bool(true)) = self.get("Desktop Entry", "DBusActivatable") {
+            let startup_id = options
+                .startup_id
+                .clone()
+                .unwrap_or_else(|| new_startup_id(&options.launcher, bus_name(desktop_file_id)));
+            let token = options.activation_token.as_ref().unwrap_or(&startup_id);
+            let platform_data = HashMap::from([
+                ("desktop-startup-id", Value::from(startup_id.as_str())),
+                ("activation-token", Value::from(token.as_str())),
+            ]);
+            if call_application(desktop_file_id, action, files, platform_data).is_ok() {
+                return Ok(Activation::DBus { startup_id });
+            }
+        }
+        Ok(Activation::Spawned(self.spawn(action, files, options)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_names() {
+        let name = bus_name("org.example.My-App.desktop");
+        assert_eq!(name, "org.example.My-App");
+        assert_eq!(object_path(name), "/org/example/My_App");
+        assert_eq!(file_uri("/tmp/a b.txt").unwrap(), "file:///tmp/a%20b.txt");
+        assert_eq!(
+            file_uri("https://example.org").unwrap(),
+            "https://example.org"
+        );
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            file_uri("foo.txt").unwrap(),
+            file_uri(cwd.join("foo.txt").to_str().unwrap()).unwrap()
+        );
+        assert!(file_uri("foo.txt").unwrap().starts_with("file:///"));
+    }
+
+    #[test]
+    fn test_fallback() {
+        let entry = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nExec=sh -c \"exit 6\"\nDBusActivatable=true\n",
+        )
+        .unwrap();
+        // Nothing owns this name, so activation falls back to Exec.
+        let id = "org.example.DoesNotExist.desktop";
+        match entry
+            .activate(id, None, &[], &LaunchOptions::new())
+            .unwrap()
+        {
+            Activation::Spawned(mut launched) => {
+                assert_eq!(launched.child.wait().unwrap().code(), Some(6))
+            }
+            other => panic!("unexpected activation: {:?}", other),
+        }
+    }
+}
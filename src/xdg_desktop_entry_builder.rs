@@ -0,0 +1,273 @@
+use crate::{
+    xdg_category::Category, xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::XdgDesktopValue,
+    xdg_entry_type::EntryType, xdg_exec::ExecCommand, xdg_parse_error::XdgParseError,
+    xdg_parse_warning::XdgParseWarning, xdg_spec_version::SpecVersion,
+};
+
+/// Builds a desktop entry from scratch.
+///
+/// ```
+/// # use xdg_desktop_parser::{EntryType, XdgDesktopEntryBuilder};
+/// let file = XdgDesktopEntryBuilder::new(EntryType::Application)
+///     .name("Foo")
+///     .exec("foo %U")
+///     .categories(["Utility"])
+///     .terminal(false)
+///     .build()
+///     .unwrap();
+/// assert_eq!(file.exec(), Some("foo %U"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct XdgDesktopEntryBuilder {
+    file: XdgDesktopFile,
+    actions: Vec<String>,
+    error: Option<XdgParseError>,
+}
+
+fn strings<S: Into<String>>(items: impl IntoIterator<Item = S>) -> XdgDesktopValue {
+    XdgDesktopValue::List(
+        items
+            .into_iter()
+            .map(|s| XdgDesktopValue::String(s.into()))
+            .collect(),
+    )
+}
+
+impl XdgDesktopEntryBuilder {
+    /// Starts an entry of type `entry_type`, conforming to version 1.5 of the specification.
+    pub fn new(entry_type: EntryType) -> XdgDesktopEntryBuilder {
+        XdgDesktopEntryBuilder {
+            file: XdgDesktopFile::default(),
+            actions: Vec::new(),
+            error: None,
+        }
+        .string("Type", entry_type.as_str())
+        .version(SpecVersion::V1_5)
+    }
+
+    fn set(mut self, section: &str, key: &str, value: XdgDesktopValue) -> Self {
+        if let Err(e) = self.file.set(section, key, value) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    fn string(self, key: &str, value: impl Into<String>) -> Self {
+        self.set("Desktop Entry", key, XdgDesktopValue::String(value.into()))
+    }
+
+    fn locale_string(self, key: &str, value: impl Into<String>) -> Self {
+        self.set(
+            "Desktop Entry",
+            key,
+            XdgDesktopValue::LocaleString(value.into()),
+        )
+    }
+
+    /// Sets any key of the `[Desktop Entry]` section, such as an `X-` extension key. An
+    /// invalid key makes [`build`](XdgDesktopEntryBuilder::build) fail.
+    pub fn key(self, key: &str, value: impl Into<XdgDesktopValue>) -> Self {
+        self.set("Desktop Entry", key, value.into())
+    }
+
+    /// Sets the translation of the localestring `key` for `locale`, e.g. `Name[de]`.
+    pub fn localized(self, key: &str, locale: &str, value: impl Into<String>) -> Self {
+        self.locale_string(&format!("{}[{}]", key, locale), value)
+    }
+
+    pub fn version(self, version: SpecVersion) -> Self {
+        self.string("Version", version.to_string())
+    }
+
+    pub fn name(self, name: impl Into<String>) -> Self {
+        self.locale_string("Name", name)
+    }
+
+    pub fn generic_name(self, name: impl Into<String>) -> Self {
+        self.locale_string("GenericName", name)
+    }
+
+    pub fn comment(self, comment: impl Into<String>) -> Self {
+        self.locale_string("Comment", comment)
+    }
+
+    pub fn icon(self, icon: impl Into<String>) -> Self {
+        self.set(
+            "Desktop Entry",
+            "Icon",
+            XdgDesktopValue::IconString(icon.into()),
+        )
+    }
+
+    pub fn exec(self, exec: impl Into<String>) -> Self {
+        self.string("Exec", exec)
+    }
+
+    pub fn try_exec(self, path: impl Into<String>) -> Self {
+        self.string("TryExec", path)
+    }
+
+    /// The working directory to run the program in.
+    pub fn path(self, path: impl Into<String>) -> Self {
+        self.string("Path", path)
+    }
+
+    pub fn url(self, url: impl Into<String>) -> Self {
+        self.string("URL", url)
+    }
+
+    pub fn startup_wm_class(self, class: impl Into<String>) -> Self {
+        self.string("StartupWMClass", class)
+    }
+
+    pub fn terminal(self, terminal: bool) -> Self {
+        self.key("Terminal", terminal)
+    }
+
+    pub fn no_display(self, no_display: bool) -> Self {
+        self.key("NoDisplay", no_display)
+    }
+
+    pub fn hidden(self, hidden: bool) -> Self {
+        self.key("Hidden", hidden)
+    }
+
+    pub fn startup_notify(self, startup_notify: bool) -> Self {
+        self.key("StartupNotify", startup_notify)
+    }
+
+    pub fn dbus_activatable(self, activatable: bool) -> Self {
+        self.key("DBusActivatable", activatable)
+    }
+
+    pub fn prefers_non_default_gpu(self, prefers: bool) -> Self {
+        self.key("PrefersNonDefaultGPU", prefers)
+    }
+
+    pub fn single_main_window(self, single: bool) -> Self {
+        self.key("SingleMainWindow", single)
+    }
+
+    pub fn categories<C: Into<Category>>(self, categories: impl IntoIterator<Item = C>) -> Self {
+        let categories = categories
+            .into_iter()
+            .map(|c| c.into().as_str().to_string());
+        self.key("Categories", strings(categories))
+    }
+
+    pub fn mime_types<S: Into<String>>(self, types: impl IntoIterator<Item = S>) -> Self {
+        self.key("MimeType", strings(types))
+    }
+
+    pub fn keywords<S: Into<String>>(self, keywords: impl IntoIterator<Item = S>) -> Self {
+        let keywords = keywords
+            .into_iter()
+            .map(|k| XdgDesktopValue::LocaleString(k.into()))
+            .collect::<Vec<_>>();
+        self.key("Keywords", keywords)
+    }
+
+    pub fn only_show_in<S: Into<String>>(self, desktops: impl IntoIterator<Item = S>) -> Self {
+        self.key("OnlyShowIn", strings(desktops))
+    }
+
+    pub fn not_show_in<S: Into<String>>(self, desktops: impl IntoIterator<Item = S>) -> Self {
+        self.key("NotShowIn", strings(desktops))
+    }
+
+    pub fn implements<S: Into<String>>(self, interfaces: impl IntoIterator<Item = S>) -> Self {
+        self.key("Implements", strings(interfaces))
+    }
+
+    /// Adds a `[Desktop Action <id>]` section and lists it in `Actions`.
+    pub fn action(mut self, id: &str, name: impl Into<String>, exec: impl Into<String>) -> Self {
+        let section = format!("Desktop Action {}", id);
+        if !self.actions.iter().any(|a| a == id) {
+            self.actions.push(id.to_string());
+        }
+        let actions = strings(self.actions.clone());
+        self.set("Desktop Entry", "Actions", actions)
+            .set(&section, "Name", XdgDesktopValue::LocaleString(name.into()))
+            .set(&section, "Exec", XdgDesktopValue::String(exec.into()))
+    }
+
+    /// Produces the entry. Fails if a key the specification requires is missing, such as
+    /// `Name`, or `Exec` for an application that isn't D-Bus activatable, if an `Exec` value
+    /// is malformed, if a key or locale given to [`key`](XdgDesktopEntryBuilder::key) or
+    /// [`localized`](XdgDesktopEntryBuilder::localized) is invalid, or if an action ID made an
+    /// invalid section name.
+    pub fn build(self) -> crate::Result<XdgDesktopFile> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        for warning in self.file.validate() {
+            if let XdgParseWarning::MissingKey { section, key } = warning {
+                return Err(XdgParseError::MissingKey(format!("{}/{}", section, key)));
+            }
+        }
+        for (_, section) in self.file.sections() {
+            if let Some(exec) = section.get_str("Exec") {
+                ExecCommand::parse(exec)?;
+            }
+        }
+        Ok(self.file)
+    }
+
+    /// Produces the entry's text; see [`build`](XdgDesktopEntryBuilder::build).
+    pub fn build_string(self) -> crate::Result<String> {
+        self.build().map(|f| f.to_string())
+    }
+}
+
+impl XdgDesktopFile {
+    /// Starts building an entry of type `entry_type`; see [`XdgDesktopEntryBuilder`].
+    pub fn builder(entry_type: EntryType) -> XdgDesktopEntryBuilder {
+        XdgDesktopEntryBuilder::new(entry_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let text = XdgDesktopEntryBuilder::new(EntryType::Application)
+            .name("Foo")
+            .localized("Name", "de", "Fu")
+            .exec("foo %U")
+            .categories([Category::Utility, "X-Foo".into()])
+            .terminal(false)
+            .action("new-window", "New Window", "foo --new-window")
+            .build_string()
+            .unwrap();
+        assert_eq!(
+            text,
+            "[Desktop Entry]\nType=Application\nVersion=1.5\nName=Foo\nName[de]=Fu\n\
+             Exec=foo %U\nCategories=Utility;X-Foo;\nTerminal=false\nActions=new-window;\n\n\
+             [Desktop Action new-window]\nName=New Window\nExec=foo --new-window\n"
+        );
+
+        let missing = XdgDesktopEntryBuilder::new(EntryType::Link)
+            .name("Foo")
+            .build();
+        assert!(matches!(missing, Err(XdgParseError::MissingKey(k)) if k == "Desktop Entry/URL"));
+        let malformed = XdgDesktopEntryBuilder::new(EntryType::Application)
+            .name("Foo")
+            .exec("foo \"unterminated")
+            .build();
+        assert!(matches!(malformed, Err(XdgParseError::InvalidExec(_))));
+        let injected = XdgDesktopEntryBuilder::new(EntryType::Application)
+            .name("Foo")
+            .exec("foo")
+            .key("X-Foo\nExec", true)
+            .build();
+        assert!(matches!(injected, Err(XdgParseError::InvalidKey(k)) if k == "X-Foo\nExec"));
+        let bad_locale = XdgDesktopEntryBuilder::new(EntryType::Application)
+            .name("Foo")
+            .exec("foo")
+            .localized("Name", "de]\n[Evil", "Fu")
+            .build();
+        assert!(matches!(bad_locale, Err(XdgParseError::InvalidKey(_))));
+    }
+}
@@ -1,72 +1,662 @@
-use crate::{xdg_desktop_value::*, xdg_parse_error::XdgParseError};
-use lazy_static::lazy_static;
-use onig::Regex;
-use std::collections::HashMap;
+use crate::{
+    xdg_base_dirs,
+    xdg_desktop_section::XdgDesktopSection,
+    xdg_desktop_value::*,
+    xdg_exec,
+    xdg_key_file::{classify, Groups, Line},
+    xdg_parse_error::XdgParseError,
+    xdg_parse_warning::XdgParseWarning,
+    xdg_parser_options::{Duplicates, MalformedLines, ParserOptions, UnknownSections},
+    xdg_section_name::{SectionKind, SectionName},
+};
+use indexmap::IndexMap;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::{self, FromStr},
+};
 
-type XdgDesktopSection = HashMap<String, crate::Result<XdgDesktopValue>>;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct XdgDesktopFile {
-    sections: HashMap<String, XdgDesktopSection>,
+    sections: IndexMap<SectionName, XdgDesktopSection>,
+    /// The original line structure of the file; only retained in lossless mode.
+    layout: Option<Vec<LayoutLine>>,
+    warnings: Vec<XdgParseWarning>,
+}
+
+/// A line of the source file, as retained by lossless parsing.
+#[derive(Debug, Clone)]
+enum LayoutLine {
+    /// A comment or blank line, reproduced verbatim.
+    Verbatim(String),
+    Header(String),
+    /// A key-value line. `text` holds the original line until the key is modified, at which
+    /// point the line is regenerated from the parsed value.
+    Entry {
+        section: SectionName,
+        key: String,
+        text: Option<String>,
+    },
+}
+
+impl FromStr for XdgDesktopFile {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::parse(s, &ParserOptions::default())
+    }
 }
 
 impl XdgDesktopFile {
-    pub fn from_str(s: &str) -> crate::Result<XdgDesktopFile> {
-        lazy_static! {
-            static ref COMMENT_RE: Regex = Regex::new("#.*").unwrap();
-            static ref SECTION_RE: Regex = Regex::new(r#"\[(.*)\]"#).unwrap();
+    /// Returns a builder for parsing with non-default options.
+    pub fn parser() -> ParserOptions {
+        ParserOptions::new()
+    }
+
+    /// Parses a file while retaining its comments, blank lines and the exact text of every
+    /// line. Serializing the result with `to_string` reproduces the input, except for lines
+    /// belonging to keys that were changed with [`set`](XdgDesktopFile::set) or
+    /// [`remove`](XdgDesktopFile::remove).
+    pub fn from_str_lossless(s: &str) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::parser().lossless(true).parse(s)
+    }
+
+    /// Parses a file that may not be valid UTF-8, replacing invalid sequences; see
+    /// [`ParserOptions::lossy_utf8`].
+    pub fn from_bytes(bytes: &[u8]) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::parser().parse_bytes(bytes)
+    }
+
+    /// Reads and parses the file at `path` with [`from_bytes`](XdgDesktopFile::from_bytes).
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::from_bytes(&fs::read(path)?)
+    }
+
+    pub(crate) fn parse_bytes(
+        bytes: &[u8],
+        options: &ParserOptions,
+    ) -> crate::Result<XdgDesktopFile> {
+        let e = match str::from_utf8(bytes) {
+            Ok(s) => return XdgDesktopFile::parse(s, options),
+            Err(e) if !options.lossy_utf8 => return Err(e.into()),
+            Err(e) => e,
+        };
+        let mut out = XdgDesktopFile::parse(&String::from_utf8_lossy(bytes), options)?;
+        // Lines before the first invalid sequence are known to be valid.
+        let first_line = bytes[..e.valid_up_to()].split(|&b| b == b'\n').count();
+        let invalid = bytes
+            .split(|&b| b == b'\n')
+            .enumerate()
+            .skip(first_line - 1)
+            .filter(|(_, line)| str::from_utf8(line).is_err())
+            .map(|(i, _)| XdgParseWarning::InvalidUtf8 { line: i + 1 });
+        out.warnings.splice(0..0, invalid);
+        Ok(out)
+    }
+
+    pub(crate) fn from_sections(
+        sections: IndexMap<SectionName, XdgDesktopSection>,
+    ) -> XdgDesktopFile {
+        XdgDesktopFile {
+            sections,
+            layout: None,
+            warnings: Vec::new(),
         }
+    }
+
+    pub(crate) fn parse(s: &str, options: &ParserOptions) -> crate::Result<XdgDesktopFile> {
         let mut out = XdgDesktopFile {
-            sections: HashMap::new(),
+            sections: IndexMap::new(),
+            layout: None,
+            warnings: Vec::new(),
         };
-        let mut current_entry = HashMap::<String, crate::Result<XdgDesktopValue>>::new();
-        let mut current_entry_header: Option<&str> = None;
-        for ln in s.lines() {
-            match ln {
-                comment if (COMMENT_RE.is_match(comment) | comment.trim().is_empty()) => {}
-                section if SECTION_RE.is_match(section) => {
-                    if current_entry_header.is_some() {
-                        out.sections
-                            .insert(current_entry_header.unwrap().to_string(), current_entry);
-                        current_entry = HashMap::new();
+        let mut layout = Vec::new();
+        let mut groups: Groups<SectionName, XdgDesktopSection> = Groups::new();
+        // Set while inside a section that is being skipped, either because it is unknown or
+        // because it is a duplicate.
+        let mut skipping = false;
+        for (i, ln) in s.lines().enumerate() {
+            let line = classify(ln);
+            match line {
+                Line::Blank | Line::Comment => layout.push(LayoutLine::Verbatim(ln.to_string())),
+                Line::Header(section) => {
+                    let name = match SectionName::new(section) {
+                        Ok(name) => name,
+                        Err(e) => {
+                            match options.malformed_lines {
+                                MalformedLines::Error => return Err(e),
+                                MalformedLines::Skip => {}
+                                MalformedLines::Keep => {
+                                    out.warnings.push(XdgParseWarning::InvalidSection {
+                                        section: section.to_string(),
+                                        line: i + 1,
+                                    })
+                                }
+                            }
+                            skipping = true;
+                            layout.push(LayoutLine::Verbatim(ln.to_string()));
+                            continue;
+                        }
+                    };
+                    if let SectionKind::Other(_) = name.kind() {
+                        match options.unknown_sections {
+                            UnknownSections::Error => {
+                                return Err(XdgParseError::UnknownSection(name.to_string()))
+                            }
+                            UnknownSections::Skip => {
+                                skipping = true;
+                                layout.push(LayoutLine::Verbatim(ln.to_string()));
+                                continue;
+                            }
+                            UnknownSections::Keep => {}
+                        }
                     }
-                    current_entry_header = Some(section)
+                    if groups.contains(&name) {
+                        match options.duplicate_sections {
+                            Duplicates::Error => {
+                                return Err(XdgParseError::DuplicateSection(name.to_string()))
+                            }
+                            policy => {
+                                out.warnings.push(XdgParseWarning::DuplicateSection {
+                                    section: name.to_string(),
+                                    line: i + 1,
+                                });
+                                if policy == Duplicates::FirstWins {
+                                    skipping = true;
+                                    layout.push(LayoutLine::Verbatim(ln.to_string()));
+                                    continue;
+                                }
+                            }
+                        }
+                    }
+                    skipping = false;
+                    groups.open(name);
+                    layout.push(LayoutLine::Header(ln.to_string()));
                 }
-                line => {
-                    if current_entry_header.is_none() {
-                        return Err(XdgParseError::Other(
-                            "File contains keys without section header",
-                        ));
+                _ if skipping => layout.push(LayoutLine::Verbatim(ln.to_string())),
+                Line::Entry(..) | Line::Malformed => {
+                    let (header, current_entry) = groups.current()?;
+                    let (k, raw) = match line {
+                        Line::Entry(k, v) => (k, Some(v)),
+                        _ => (ln, None),
+                    };
+                    let malformed = match raw {
+                        Some(_) => {
+                            (options.validate_locales && !has_valid_locale(k))
+                                || (options.validate_keys && !is_valid_key(k))
+                        }
+                        None => true,
+                    };
+                    if malformed {
+                        match options.malformed_lines {
+                            MalformedLines::Error => {
+                                return Err(XdgParseError::MalformedLine(ln.to_string()))
+                            }
+                            MalformedLines::Skip => {
+                                layout.push(LayoutLine::Verbatim(ln.to_string()));
+                                continue;
+                            }
+                            MalformedLines::Keep => {}
+                        }
+                    }
+                    if current_entry.has_key(k) {
+                        if options.duplicate_keys == Duplicates::Error {
+                            return Err(XdgParseError::DuplicateKey(k.to_string()));
+                        }
+                        out.warnings.push(XdgParseWarning::DuplicateKey {
+                            section: header.to_string(),
+                            key: k.to_string(),
+                            line: i + 1,
+                        });
+                        if options.duplicate_keys == Duplicates::FirstWins {
+                            layout.push(LayoutLine::Verbatim(ln.to_string()));
+                            continue;
+                        }
+                    }
+                    let v = match raw {
+                        None => Err(XdgParseError::Other("No delimiter found in line")),
+                        Some(_) if malformed => Err(XdgParseError::MalformedLine(ln.to_string())),
+                        Some(raw) => XdgDesktopValue::from_key_value(k, raw, options),
+                    };
+                    if matches!(v, Ok(XdgDesktopValue::Bool(_)))
+                        && !matches!(raw, Some("true") | Some("false"))
+                    {
+                        out.warnings.push(XdgParseWarning::LegacyBoolean {
+                            section: header.to_string(),
+                            key: k.to_string(),
+                            line: i + 1,
+                        });
                     }
-                    let (k, v) = XdgDesktopValue::from_kv(line);
-                    current_entry.insert(k.to_string(), v);
+                    current_entry.insert_parsed(k, v, raw);
+                    layout.push(LayoutLine::Entry {
+                        section: header.clone(),
+                        key: k.to_string(),
+                        text: Some(ln.to_string()),
+                    });
                 }
             }
         }
-        if !current_entry.is_empty() {
-            match current_entry_header {
-                Some(s) => {
-                    out.sections.insert(s.to_string(), current_entry);
+        out.sections = groups.into_map();
+        if options.lossless {
+            out.layout = Some(layout);
+        }
+        Ok(out)
+    }
+
+    /// Problems found while parsing that didn't cause it to fail.
+    pub fn warnings(&self) -> &[XdgParseWarning] {
+        &self.warnings
+    }
+
+    /// Iterates over every key whose value failed to parse, with its section and the reason,
+    /// in the order the sections appear.
+    pub fn errors(&self) -> impl Iterator<Item = (&SectionName, &str, &XdgParseError)> {
+        self.sections
+            .iter()
+            .flat_map(|(name, section)| section.diagnostics().map(move |(k, e)| (name, k, e)))
+    }
+
+    /// Iterates over the sections in the order they appear in the source file. The keys
+    /// within each section are likewise kept in their original order.
+    pub fn sections(&self) -> impl Iterator<Item = (&SectionName, &XdgDesktopSection)> {
+        self.sections.iter()
+    }
+
+    /// Sets `key` in `section` to `value`, creating the section if it doesn't exist. Existing
+    /// keys keep their position; new keys are added at the end of their section. Fails if
+    /// `section` is not a valid section name, or `key` is not a valid key, optionally with a
+    /// locale suffix such as `Name[de]`.
+    pub fn set(&mut self, section: &str, key: &str, value: XdgDesktopValue) -> crate::Result<()> {
+        if !is_writable_key(key) {
+            return Err(XdgParseError::InvalidKey(key.to_string()));
+        }
+        self.set_unchecked(section, key, value)
+    }
+
+    /// Like [`set`](XdgDesktopFile::set), but accepts any key, for keys taken from a parsed
+    /// file, which are written back as they were read.
+    pub(crate) fn set_unchecked(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: XdgDesktopValue,
+    ) -> crate::Result<()> {
+        let section = SectionName::new(section)?;
+        let is_new_section = !self.sections.contains_key(&section);
+        let is_new_key = self
+            .sections
+            .entry(section.clone())
+            .or_default()
+            .set(key, value);
+        let layout = match self.layout.as_mut() {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        if is_new_section {
+            if !layout.is_empty() {
+                layout.push(LayoutLine::Verbatim(String::new()));
+            }
+            layout.push(LayoutLine::Header(format!("[{}]", section)));
+        }
+        let new_line = LayoutLine::Entry {
+            section: section.clone(),
+            key: key.to_string(),
+            text: None,
+        };
+        if is_new_key {
+            // Insert after the last line that belongs to the section, leaving any trailing
+            // comments and blank lines where they are.
+            let mut insert_at = layout.len();
+            let mut in_section = false;
+            for (i, l) in layout.iter().enumerate() {
+                if let LayoutLine::Header(h) = l {
+                    in_section = SectionName::from_header(h).is_ok_and(|h| h == section);
                 }
-                None => {
-                    return Err(XdgParseError::Other(
-                        "File contains keys without section header",
-                    ))
+                if in_section && !matches!(l, LayoutLine::Verbatim(_)) {
+                    insert_at = i + 1;
                 }
             }
+            layout.insert(insert_at, new_line);
+        } else {
+            let mut seen = false;
+            layout.retain_mut(|l| match l {
+                LayoutLine::Entry {
+                    section: s, key: k, ..
+                } if *s == section && k == key => {
+                    if seen {
+                        return false;
+                    }
+                    seen = true;
+                    *l = new_line.clone();
+                    true
+                }
+                _ => true,
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes `key` from `section`, returning its value if it was present.
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<XdgDesktopValue> {
+        let removed = self.sections.get_mut(section)?.remove(key);
+        if let Some(layout) = self.layout.as_mut() {
+            layout.retain(|l| {
+                !matches!(l, LayoutLine::Entry { section: s, key: k, .. } if s.as_str() == section && k == key)
+            });
+        }
+        removed
+    }
+
+    /// Rewrites the file in canonical form, as an autoformatter would: `[Desktop Entry]`
+    /// first, then the `[Desktop Action]` sections, then any others; within each section,
+    /// the keys the specification defines in the order it lists them, then any others, each
+    /// followed by its translations sorted by locale. Values are written back from their
+    /// parsed form, so lists end in a `;`, and there is no space around the `=`. Values that
+    /// failed to parse are kept as they are.
+    ///
+    /// Comments retained by lossless parsing move with the header or key that follows them;
+    /// those before the first header stay at the top of the file. Blank lines, and lines
+    /// that were skipped while parsing, are dropped.
+    ///
+    /// ```
+    /// # use xdg_desktop_parser::XdgDesktopFile;
+    /// let mut file: XdgDesktopFile = "[Desktop Entry]\nName[de] = Dateien\nCategories=System\nName=Files\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// file.normalize();
+    /// assert_eq!(
+    ///     file.to_string(),
+    ///     "[Desktop Entry]\nName=Files\nName[de]=Dateien\nCategories=System;\n"
+    /// );
+    /// ```
+    pub fn normalize(&mut self) {
+        self.sections
+            .sort_by_cached_key(|name, _| match name.kind() {
+                SectionKind::DesktopEntry => 0,
+                SectionKind::DesktopAction(_) => 1,
+                _ => 2,
+            });
+        for section in self.sections.values_mut() {
+            section.normalize();
+        }
+        if let Some(layout) = self.layout.take() {
+            self.layout = Some(self.normalized_layout(layout));
+        }
+    }
+
+    /// Lays out the sections and keys in their current order, keeping the comments of
+    /// `layout` with the lines that followed them.
+    fn normalized_layout(&self, layout: Vec<LayoutLine>) -> Vec<LayoutLine> {
+        // Comments, keyed by the section and key, or the header when the key is `None`, of
+        // the line that followed them.
+        let mut comments: IndexMap<(SectionName, Option<String>), Vec<String>> = IndexMap::new();
+        let mut preamble = Vec::new();
+        let mut pending = Vec::new();
+        let mut seen_header = false;
+        for l in layout {
+            let anchor = match l {
+                LayoutLine::Verbatim(text) => {
+                    if matches!(classify(&text), Line::Comment) {
+                        pending.push(text);
+                    }
+                    continue;
+                }
+                LayoutLine::Header(text) => {
+                    if !seen_header {
+                        seen_header = true;
+                        preamble.append(&mut pending);
+                    }
+                    match SectionName::from_header(&text) {
+                        Ok(name) => (name, None),
+                        Err(_) => continue,
+                    }
+                }
+                LayoutLine::Entry { section, key, .. } => (section, Some(key)),
+            };
+            if !pending.is_empty() {
+                comments.entry(anchor).or_default().append(&mut pending);
+            }
+        }
+        let mut comments_for = |section: &SectionName, key: Option<&str>| {
+            comments
+                .swap_remove(&(section.clone(), key.map(str::to_string)))
+                .unwrap_or_default()
+                .into_iter()
+                .map(LayoutLine::Verbatim)
+        };
+        let mut out: Vec<LayoutLine> = preamble.into_iter().map(LayoutLine::Verbatim).collect();
+        for (name, section) in &self.sections {
+            if !out.is_empty() {
+                out.push(LayoutLine::Verbatim(String::new()));
+            }
+            out.extend(comments_for(name, None));
+            out.push(LayoutLine::Header(format!("[{}]", name)));
+            for (key, _) in section.entries() {
+                out.extend(comments_for(name, Some(key)));
+                out.push(LayoutLine::Entry {
+                    section: name.clone(),
+                    key: key.to_string(),
+                    text: None,
+                });
+            }
+        }
+        // Comments after the last line, or on keys that are gone.
+        let rest: Vec<String> = comments.into_values().flatten().chain(pending).collect();
+        out.extend(rest.into_iter().map(LayoutLine::Verbatim));
+        out
+    }
+
+    /// The text of `key`'s value as it appeared in the source file, before unescaping or
+    /// type conversion. This is available even for values that failed to parse. For keys
+    /// changed with [`set`](XdgDesktopFile::set), it is the new value's serialized form.
+    pub fn raw_value(&self, section: &str, key: impl AsRef<str>) -> Option<&str> {
+        self.section(section)?.raw_value(key)
+    }
+
+    pub fn section(&self, name: &str) -> Option<&XdgDesktopSection> {
+        self.sections.get(name)
+    }
+
+    /// Looks up the value of `key` in `section`. Returns `None` if the key is missing or its
+    /// value failed to parse; see [`XdgDesktopSection::diagnostics`] for the latter.
+    pub fn get(&self, section: &str, key: impl AsRef<str>) -> Option<&XdgDesktopValue> {
+        self.section(section)?.get(key)
+    }
+
+    /// Looks up a string, localestring or iconstring value.
+    pub fn get_str(&self, section: &str, key: impl AsRef<str>) -> Option<&str> {
+        self.section(section)?.get_str(key)
+    }
+
+    pub fn get_bool(&self, section: &str, key: impl AsRef<str>) -> Option<bool> {
+        self.section(section)?.get_bool(key)
+    }
+
+    pub fn get_numeric(&self, section: &str, key: impl AsRef<str>) -> Option<f64> {
+        self.section(section)?.get_numeric(key)
+    }
+
+    pub fn get_list(&self, section: &str, key: impl AsRef<str>) -> Option<&[XdgDesktopValue]> {
+        self.section(section)?.get_list(key)
+    }
+
+    fn desktop_entry_value(&self, key: &str) -> Option<&XdgDesktopValue> {
+        self.get("Desktop Entry", key)
+    }
+
+    fn desktop_entry_bool(&self, key: &str) -> bool {
+        self.get_bool("Desktop Entry", key) == Some(true)
+    }
+
+    /// Applies `OnlyShowIn` and `NotShowIn` to `desktops`, a list of desktop environment
+    /// names such as `$XDG_CURRENT_DESKTOP`'s.
+    fn show_in_allows(&self, desktops: &[&str]) -> bool {
+        let lists = |key| match self.desktop_entry_value(key) {
+            Some(XdgDesktopValue::List(l)) => Some(l),
+            _ => None,
+        };
+        let names_any = |l: &Vec<XdgDesktopValue>| {
+            l.iter()
+                .any(|v| matches!(v, XdgDesktopValue::String(s) if desktops.contains(&s.as_str())))
+        };
+        if let Some(only) = lists("OnlyShowIn") {
+            return names_any(only);
+        }
+        !lists("NotShowIn").is_some_and(names_any)
+    }
+
+    /// Whether a menu or launcher running in the desktop environments `desktops` (as listed
+    /// by `$XDG_CURRENT_DESKTOP`) should display this entry. Entries with `NoDisplay=true` or
+    /// `Hidden=true` are never shown; otherwise `OnlyShowIn` requires one of `desktops` to be
+    /// listed, and `NotShowIn` requires none to be.
+    pub fn is_shown_in(&self, desktops: &[&str]) -> bool {
+        !self.desktop_entry_bool("NoDisplay")
+            && !self.desktop_entry_bool("Hidden")
+            && self.show_in_allows(desktops)
+    }
+
+    /// [`is_shown_in`](XdgDesktopFile::is_shown_in) the desktops named by
+    /// `$XDG_CURRENT_DESKTOP`.
+    pub fn is_shown(&self) -> bool {
+        let desktops = xdg_base_dirs::current_desktops();
+        let desktops: Vec<&str> = desktops.iter().map(String::as_str).collect();
+        self.is_shown_in(&desktops)
+    }
+
+    /// Resolves the program named by `TryExec`, returning its path if it exists and is
+    /// executable. Per the specification, entries whose `TryExec` program can't be found
+    /// should be ignored, as the application is probably not installed. Returns `None` when
+    /// there is no `TryExec` key as well.
+    pub fn try_exec_resolves(&self) -> Option<PathBuf> {
+        match self.desktop_entry_value("TryExec")? {
+            XdgDesktopValue::String(program) => xdg_exec::find_executable(program),
+            _ => None,
         }
-        Ok(out)
     }
 
-    pub fn sections(&self) -> impl Iterator<Item = (&str, &XdgDesktopSection)> {
-        self.sections.iter().map(|(k, v)| (k.as_ref(), v))
+    /// Whether this entry, found in an autostart directory (see
+    /// [`Discovery::autostart`](crate::discovery::Discovery::autostart)), should be started
+    /// when logging into `current_desktop`, which may be a colon-separated list like
+    /// `$XDG_CURRENT_DESKTOP`. Per the Desktop Application Autostart specification, entries
+    /// with `Hidden=true` are skipped and `OnlyShowIn`/`NotShowIn` are honored.
+    pub fn should_autostart(&self, current_desktop: &str) -> bool {
+        let desktops: Vec<&str> = current_desktop
+            .split(':')
+            .filter(|d| !d.is_empty())
+            .collect();
+        !self.desktop_entry_bool("Hidden") && self.show_in_allows(&desktops)
     }
 }
 
-#[cfg(test)]
+/// Writes a key, with its parsed value if it has one. Values that failed to parse are written
+/// back as they were read.
+fn write_entry(
+    f: &mut fmt::Formatter<'_>,
+    key: &str,
+    value: Result<&XdgDesktopValue, &str>,
+) -> fmt::Result {
+    match value {
+        Ok(v) => writeln!(f, "{}={}", key, v),
+        Err(raw) => writeln!(f, "{}={}", key, raw),
+    }
+}
+
+impl fmt::Display for XdgDesktopFile {
+    /// Serializes the file. Files parsed in lossless mode reproduce their original layout;
+    /// otherwise the sections and keys are written out in order, with values that failed to
+    /// parse written as they were read.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.layout {
+            Some(layout) => {
+                for l in layout {
+                    match l {
+                        LayoutLine::Verbatim(text)
+                        | LayoutLine::Header(text)
+                        | LayoutLine::Entry {
+                            text: Some(text), ..
+                        } => writeln!(f, "{}", text)?,
+                        LayoutLine::Entry {
+                            section,
+                            key,
+                            text: None,
+                        } => {
+                            let section = match self.sections.get(section) {
+                                Some(s) => s,
+                                None => continue,
+                            };
+                            if let Some(v) = section.get(key) {
+                                write_entry(f, key, Ok(v))?;
+                            } else if let Some(raw) = section.raw_value(key) {
+                                write_entry(f, key, Err(raw))?;
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                for (i, (name, section)) in self.sections.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    writeln!(f, "[{}]", name)?;
+                    for (k, v) in section.entries() {
+                        write_entry(f, k, v)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files are serialized as a map of section names to maps of keys to values. Keys whose values
+/// failed to parse are omitted, as are the warnings and the lossless layout.
+#[cfg(feature = "serde")]
+impl serde::Serialize for XdgDesktopFile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        struct Section<'a>(&'a XdgDesktopSection);
+
+        impl serde::Serialize for Section<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(self.0.iter())
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(self.sections.len()))?;
+        for (name, section) in &self.sections {
+            map.serialize_entry(name.as_str(), &Section(section))?;
+        }
+        map.end()
+    }
+}
 
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for XdgDesktopFile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = IndexMap::<String, IndexMap<String, XdgDesktopValue>>::deserialize(deserializer)?;
+        let mut sections = IndexMap::with_capacity(raw.len());
+        for (name, keys) in raw {
+            let name = SectionName::new(&name).map_err(serde::de::Error::custom)?;
+            let mut section = XdgDesktopSection::new();
+            for (k, v) in keys {
+                section.set(&k, v);
+            }
+            sections.insert(name, section);
+        }
+        Ok(XdgDesktopFile {
+            sections,
+            layout: None,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::fs::read_to_string;
 
     #[test]
@@ -80,11 +670,391 @@ mod tests {
             let contents = read_to_string(f).unwrap();
             let parsed = XdgDesktopFile::from_str(&contents);
             assert!(parsed.is_ok());
-            for grp in parsed.unwrap().sections() {
-                for (_, v) in grp.1.iter() {
-                    assert!(v.is_ok())
-                }
+            assert!(parsed.unwrap().errors().next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_order_preserved() {
+        let contents = read_to_string("test/Alacritty.desktop").unwrap();
+        let parsed = XdgDesktopFile::from_str(&contents).unwrap();
+        let sections: Vec<&str> = parsed.sections().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(sections, ["Desktop Entry", "Desktop Action New"]);
+        let keys: Vec<&str> = parsed.sections().next().unwrap().1.keys().collect();
+        assert_eq!(
+            keys,
+            [
+                "Type",
+                "TryExec",
+                "Exec",
+                "Icon",
+                "Terminal",
+                "Categories",
+                "Name",
+                "GenericName",
+                "Comment",
+                "StartupWMClass",
+                "Actions",
+                "X-Desktop-File-Install-Version",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let contents = read_to_string("test/Alacritty.desktop").unwrap();
+        let mut parsed = XdgDesktopFile::from_str_lossless(&contents).unwrap();
+        assert_eq!(parsed.to_string(), contents);
+        parsed
+            .set("Desktop Entry", "Terminal", true.into())
+            .unwrap();
+        parsed
+            .set(
+                "Desktop Action New",
+                "Icon",
+                XdgDesktopValue::IconString("New".into()),
+            )
+            .unwrap();
+        let expected = contents.replace("Terminal=false", "Terminal=true") + "Icon=New\n";
+        assert_eq!(parsed.to_string(), expected);
+    }
+
+    #[test]
+    fn test_set_invalid_key() {
+        let mut file = XdgDesktopFile::default();
+        file.set(
+            "Desktop Entry",
+            "Name[de]",
+            XdgDesktopValue::LocaleString("Fu".into()),
+        )
+        .unwrap();
+        for key in [
+            "",
+            "X-Foo\nExec=evil",
+            "Name=x",
+            "Name[de]\n[Evil]",
+            "Name[de]x",
+            "X Foo",
+        ] {
+            assert!(
+                matches!(
+                    file.set("Desktop Entry", key, true.into()),
+                    Err(XdgParseError::InvalidKey(k)) if k == key
+                ),
+                "{:?}",
+                key
+            );
+        }
+        assert_eq!(file.to_string(), "[Desktop Entry]\nName[de]=Fu\n");
+    }
+
+    #[test]
+    fn test_parser_policies() {
+        let input = "[Desktop Entry]\nName=First\nName=Second\nBogus\n\n[Unknown]\nKey=Value\n";
+        assert!(XdgDesktopFile::parser().strict(true).parse(input).is_err());
+
+        let parsed = XdgDesktopFile::parser()
+            .duplicate_keys(Duplicates::FirstWins)
+            .malformed_lines(MalformedLines::Skip)
+            .unknown_sections(UnknownSections::Skip)
+            .parse(input)
+            .unwrap();
+        let sections: Vec<_> = parsed.sections().collect();
+        assert_eq!(sections.len(), 1);
+        let entry = sections[0].1;
+        assert_eq!(entry.len(), 1);
+        assert_eq!(entry.get("Name").unwrap().to_string(), "First");
+
+        let parsed = XdgDesktopFile::from_str(input).unwrap();
+        let entry = parsed.sections().next().unwrap().1;
+        assert_eq!(entry.get("Name").unwrap().to_string(), "Second");
+        assert!(entry.diagnostics().any(|(k, _)| k == "Bogus"));
+        assert_eq!(parsed.sections().count(), 2);
+
+        let input = "[Desktop Entry]\nName=Foo\n[Bad]Name]\nName=Bar\n";
+        let parse = |policy| {
+            XdgDesktopFile::parser()
+                .malformed_lines(policy)
+                .parse(input)
+        };
+        assert!(matches!(
+            parse(MalformedLines::Error),
+            Err(XdgParseError::InvalidSectionName(_))
+        ));
+        let skipped = parse(MalformedLines::Skip).unwrap();
+        assert_eq!(skipped.sections().count(), 1);
+        assert_eq!(skipped.get_str("Desktop Entry", "Name"), Some("Foo"));
+        assert!(skipped.warnings().is_empty());
+        let kept = XdgDesktopFile::from_str_lossless(input).unwrap();
+        assert_eq!(kept.get_str("Desktop Entry", "Name"), Some("Foo"));
+        assert_eq!(
+            kept.warnings(),
+            [XdgParseWarning::InvalidSection {
+                section: "Bad]Name".to_string(),
+                line: 3
+            }]
+        );
+        assert_eq!(kept.to_string(), input);
+    }
+
+    #[test]
+    fn test_key_whitespace() {
+        let input = "[Desktop Entry]\nName = Foo\nExec\t=foo \nX_Bad.Key=1\n";
+        let parsed = XdgDesktopFile::from_str(input).unwrap();
+        assert_eq!(parsed.get_str("Desktop Entry", "Name"), Some("Foo"));
+        assert_eq!(parsed.get_str("Desktop Entry", "Exec"), Some("foo "));
+        assert!(parsed.get("Desktop Entry", "X_Bad.Key").is_some());
+        let strict = XdgDesktopFile::parser()
+            .validate_keys(true)
+            .parse(input)
+            .unwrap();
+        assert!(strict.get("Desktop Entry", "X_Bad.Key").is_none());
+        assert_eq!(strict.raw_value("Desktop Entry", "X_Bad.Key"), Some("1"));
+        let strict = XdgDesktopFile::parser().strict(true).parse(input);
+        assert!(matches!(strict, Err(XdgParseError::MalformedLine(_))));
+    }
+
+    #[test]
+    fn test_getters() {
+        let contents = read_to_string("test/Alacritty.desktop").unwrap();
+        let parsed = XdgDesktopFile::from_str(&contents).unwrap();
+        assert_eq!(parsed.get_str("Desktop Entry", "Name"), Some("Alacritty"));
+        assert_eq!(parsed.get_bool("Desktop Entry", "Terminal"), Some(false));
+        assert_eq!(parsed.get_bool("Desktop Entry", "Name"), None);
+        assert_eq!(
+            parsed
+                .get_list("Desktop Entry", "Categories")
+                .map(<[_]>::len),
+            Some(2)
+        );
+        assert!(parsed.get("Desktop Action New", "Exec").is_some());
+        assert!(parsed.get("Desktop Entry", "Missing").is_none());
+    }
+
+    #[test]
+    fn test_raw_value() {
+        let mut parsed =
+            XdgDesktopFile::from_str("[Desktop Entry]\nTerminal=maybe\nVersion=1.5\n").unwrap();
+        assert_eq!(parsed.raw_value("Desktop Entry", "Terminal"), Some("maybe"));
+        assert_eq!(parsed.raw_value("Desktop Entry", "Version"), Some("1.5"));
+        assert_eq!(parsed.raw_value("Desktop Entry", "Name"), None);
+        assert_eq!(
+            parsed.to_string(),
+            "[Desktop Entry]\nTerminal=maybe\nVersion=1.5\n"
+        );
+        parsed
+            .set("Desktop Entry", "Terminal", true.into())
+            .unwrap();
+        assert_eq!(parsed.raw_value("Desktop Entry", "Terminal"), Some("true"));
+        parsed.remove("Desktop Entry", "Version");
+        assert_eq!(parsed.raw_value("Desktop Entry", "Version"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let parsed = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nTerminal=maybe\nName=Foo\n\n[Desktop Action New]\nNoDisplay=2\n",
+        )
+        .unwrap();
+        let errors: Vec<(&str, &str)> = parsed
+            .errors()
+            .map(|(section, key, _)| (section.as_str(), key))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("Desktop Entry", "Terminal"),
+                ("Desktop Action New", "NoDisplay")
+            ]
+        );
+        let (_, _, e) = parsed.errors().next().unwrap();
+        assert!(matches!(e, XdgParseError::ParseBoolError(_)));
+    }
+
+    #[test]
+    fn test_legacy_booleans() {
+        let input = "[Desktop Entry]\nTerminal=1\nNoDisplay=True\nHidden=false\n";
+        let parsed = XdgDesktopFile::from_str(input).unwrap();
+        assert_eq!(parsed.get_bool("Desktop Entry", "Terminal"), Some(true));
+        assert_eq!(parsed.get_bool("Desktop Entry", "NoDisplay"), Some(true));
+        let lines: Vec<usize> = parsed
+            .warnings()
+            .iter()
+            .map(|w| match w {
+                XdgParseWarning::LegacyBoolean { line, .. } => *line,
+                w => panic!("unexpected warning {}", w),
+            })
+            .collect();
+        assert_eq!(lines, [2, 3]);
+
+        let strict = XdgDesktopFile::parser()
+            .legacy_booleans(false)
+            .parse(input)
+            .unwrap();
+        assert_eq!(strict.get_bool("Desktop Entry", "Terminal"), None);
+        assert!(strict.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let input = b"[Desktop Entry]\nName=Caf\xe9\nComment=ok\nGenericName=\xff\n";
+        let lossy = XdgDesktopFile::from_bytes(input).unwrap();
+        assert_eq!(lossy.get_str("Desktop Entry", "Name"), Some("Caf\u{fffd}"));
+        assert_eq!(
+            lossy.warnings(),
+            [
+                XdgParseWarning::InvalidUtf8 { line: 2 },
+                XdgParseWarning::InvalidUtf8 { line: 4 }
+            ]
+        );
+        match XdgDesktopFile::parser().strict(true).parse_bytes(input) {
+            Err(XdgParseError::InvalidUtf8(e)) => assert_eq!(e.valid_up_to(), 24),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_bytes_dont_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = XdgDesktopFile::from_bytes(&bytes);
+            let _ = XdgDesktopFile::parser().strict(true).lossless(true).parse_bytes(&bytes);
+        }
+
+        #[test]
+        fn test_arbitrary_lines_dont_panic(s in r"([\[\]=;#@_ a-zA-Z0-9\\\n\u{e9}-]{0,40}\n){0,16}") {
+            if let Ok(file) = XdgDesktopFile::from_str(&s) {
+                let _ = file.validate();
+                let _ = XdgDesktopFile::from_str(&file.to_string());
             }
+            let _ = XdgDesktopFile::from_str_lossless(&s).map(|f| f.to_string());
+            let _ = crate::KeyFile::from_str(&s);
+            let _ = crate::trashinfo::TrashInfo::from_str(&s);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_warnings() {
+        let input = "[Desktop Entry]\nType=Application\nName=A\n[Desktop Entry]\nName=B\nName=C\n";
+        let parsed = XdgDesktopFile::parser()
+            .duplicate_sections(Duplicates::FirstWins)
+            .parse(input)
+            .unwrap();
+        assert_eq!(
+            parsed.warnings(),
+            [XdgParseWarning::DuplicateSection {
+                section: "Desktop Entry".into(),
+                line: 4
+            }]
+        );
+        let entry = parsed.sections().next().unwrap().1;
+        assert_eq!(entry.get("Name").unwrap().to_string(), "A");
+
+        // By default, the repeated section is merged into the first, as `KeyFile` does.
+        let parsed = XdgDesktopFile::from_str(input).unwrap();
+        assert_eq!(parsed.warnings().len(), 3);
+        let key_file = crate::KeyFile::from_str(input).unwrap();
+        let groups: Vec<&SectionName> = key_file.groups().collect();
+        assert_eq!(
+            groups,
+            parsed.sections().map(|(n, _)| n).collect::<Vec<_>>()
+        );
+        let entry = parsed.sections().next().unwrap().1;
+        let keys: Vec<&str> = entry.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, key_file.keys("Desktop Entry").collect::<Vec<_>>());
+        for key in keys {
+            assert_eq!(
+                parsed.raw_value("Desktop Entry", key),
+                key_file.raw_value("Desktop Entry", key)
+            );
         }
+        assert_eq!(entry.get("Name").unwrap().to_string(), "C");
+        let lossless = XdgDesktopFile::from_str_lossless(input).unwrap();
+        assert_eq!(lossless.to_string(), input);
+        assert!(XdgDesktopFile::parser()
+            .duplicate_sections(Duplicates::Error)
+            .parse(input)
+            .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let contents = read_to_string("test/htop.desktop").unwrap();
+        let parsed = XdgDesktopFile::from_str(&contents).unwrap();
+        let json = serde_json::to_string(&parsed).unwrap();
+        let restored: XdgDesktopFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_string(), parsed.to_string());
+    }
+
+    #[test]
+    fn test_should_autostart() {
+        let parse = |s: &str| XdgDesktopFile::from_str(s).unwrap();
+        let plain = parse("[Desktop Entry]\nExec=foo\n");
+        assert!(plain.should_autostart("GNOME"));
+        let hidden = parse("[Desktop Entry]\nExec=foo\nHidden=true\n");
+        assert!(!hidden.should_autostart("GNOME"));
+        let only = parse("[Desktop Entry]\nExec=foo\nOnlyShowIn=KDE;XFCE;\n");
+        assert!(only.should_autostart("ubuntu:KDE"));
+        assert!(!only.should_autostart("GNOME"));
+        let not = parse("[Desktop Entry]\nExec=foo\nNotShowIn=GNOME;\n");
+        assert!(!not.should_autostart("ubuntu:GNOME"));
+        assert!(not.should_autostart("KDE"));
+    }
+
+    #[test]
+    fn test_is_shown_in() {
+        let parse = |s: &str| XdgDesktopFile::from_str(s).unwrap();
+        let entry = parse("[Desktop Entry]\nName=Foo\nOnlyShowIn=GNOME;\n");
+        assert!(entry.is_shown_in(&["ubuntu", "GNOME"]));
+        assert!(!entry.is_shown_in(&["KDE"]));
+        assert!(!entry.is_shown_in(&[]));
+        let no_display = parse("[Desktop Entry]\nName=Foo\nNoDisplay=true\n");
+        assert!(!no_display.is_shown_in(&["GNOME"]));
+        // NoDisplay only affects menus; the entry may still autostart.
+        assert!(no_display.should_autostart("GNOME"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut file = XdgDesktopFile::from_str_lossless(
+            "# Comment\n[X-Extra]\nFoo=bar\n\n[Desktop Action New]\nName=New\nExec=app --new\n\n\
+             [Desktop Entry]\nX-Custom = 1\nKeywords[fr]=a;b\nName[fr]=Fichiers\nTerminal=maybe\n\
+             Keywords=x;y\nName[de]=Dateien\nType=Application\nName=Files\nX-Custom[de]=2\n",
+        )
+        .unwrap();
+        file.normalize();
+        let expected = "# Comment\n\n[Desktop Entry]\nType=Application\nName=Files\n\
+                        Name[de]=Dateien\nName[fr]=Fichiers\nTerminal=maybe\nKeywords=x;y;\n\
+                        Keywords[fr]=a;b;\nX-Custom=1\nX-Custom[de]=2\n\n[Desktop Action New]\n\
+                        Name=New\nExec=app --new\n\n[X-Extra]\nFoo=bar\n";
+        assert_eq!(file.to_string(), expected);
+        assert_eq!(file.raw_value("Desktop Entry", "Keywords"), Some("x;y;"));
+        let mut reparsed = XdgDesktopFile::from_str_lossless(expected).unwrap();
+        reparsed.normalize();
+        assert_eq!(reparsed.to_string(), expected);
+
+        let mut commented = XdgDesktopFile::from_str_lossless(
+            "[X-Extra]\n# Extra\nFoo=bar\n\n# Main section\n[Desktop Entry]\n\
+             # Translators: the app's name\nName[de]=Dateien\nName=Files\n# The end\n",
+        )
+        .unwrap();
+        commented.normalize();
+        assert_eq!(
+            commented.to_string(),
+            "# Main section\n[Desktop Entry]\nName=Files\n# Translators: the app's name\n\
+             Name[de]=Dateien\n\n[X-Extra]\n# Extra\nFoo=bar\n# The end\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_try_exec_resolves() {
+        let parse = |s: &str| XdgDesktopFile::from_str(s).unwrap();
+        let sh = parse("[Desktop Entry]\nTryExec=sh\n");
+        assert!(sh.try_exec_resolves().unwrap().ends_with("sh"));
+        let absolute = parse("[Desktop Entry]\nTryExec=/bin/sh\n");
+        assert_eq!(absolute.try_exec_resolves(), Some(PathBuf::from("/bin/sh")));
+        let missing = parse("[Desktop Entry]\nTryExec=/nonexistent/program\n");
+        assert_eq!(missing.try_exec_resolves(), None);
     }
 }
@@ -0,0 +1,109 @@
+use crate::{
+    xdg_desktop_value::XdgDesktopValue,
+    xdg_key_file::{classify, Groups, Line},
+    xdg_parser_options::ParserOptions,
+    xdg_section_name,
+};
+use indexmap::IndexMap;
+
+/// Keys of a section and their unparsed values, in the order they appear in the source file.
+type XdgDesktopSectionRef<'a> = IndexMap<&'a str, &'a str>;
+
+/// A desktop file whose section names, keys and values borrow from the text it was parsed
+/// from. Parsing one allocates only the maps indexing the text, which makes it much cheaper
+/// than [`XdgDesktopFile`](crate::XdgDesktopFile) when scanning many files for a few keys.
+/// Values are converted to [`XdgDesktopValue`]s on request.
+///
+/// Parsing follows [`ParserOptions`]' defaults, except that lines that are neither
+/// comments, section headers nor key-value pairs are ignored, as are headers with invalid
+/// names and their keys.
+#[derive(Debug, Clone, Default)]
+pub struct XdgDesktopFileRef<'a> {
+    sections: IndexMap<&'a str, XdgDesktopSectionRef<'a>>,
+}
+
+impl<'a> XdgDesktopFileRef<'a> {
+    pub fn parse(s: &'a str) -> crate::Result<XdgDesktopFileRef<'a>> {
+        let mut groups: Groups<&str, XdgDesktopSectionRef> = Groups::new();
+        // Set while inside a section with an invalid name.
+        let mut skipping = false;
+        for ln in s.lines() {
+            match classify(ln) {
+                Line::Header(name) => {
+                    skipping = !xdg_section_name::is_valid(name);
+                    if !skipping {
+                        groups.open(name);
+                    }
+                }
+                Line::Entry(..) if skipping => {}
+                Line::Entry(k, v) => {
+                    groups.current()?.1.insert(k, v);
+                }
+                Line::Blank | Line::Comment | Line::Malformed => {}
+            }
+        }
+        Ok(XdgDesktopFileRef {
+            sections: groups.into_map(),
+        })
+    }
+
+    /// Iterates over the sections in the order they appear in the source file.
+    pub fn sections(&self) -> impl Iterator<Item = (&'a str, &XdgDesktopSectionRef<'a>)> {
+        self.sections.iter().map(|(name, keys)| (*name, keys))
+    }
+
+    /// The text of `key`'s value, as it appears in the source file.
+    pub fn raw_value(&self, section: &str, key: &str) -> Option<&'a str> {
+        self.sections.get(section)?.get(key).copied()
+    }
+
+    /// Parses the value of `key` as [`XdgDesktopFile`](crate::XdgDesktopFile) would.
+    pub fn value(&self, section: &str, key: &str) -> Option<crate::Result<XdgDesktopValue>> {
+        let raw = self.raw_value(section, key)?;
+        Some(XdgDesktopValue::from_key_value(
+            key,
+            raw,
+            &ParserOptions::default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XdgDesktopFile;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_matches_owned() {
+        for f in &["test/Alacritty.desktop", "test/htop.desktop"] {
+            let contents = read_to_string(f).unwrap();
+            let owned: XdgDesktopFile = contents.parse().unwrap();
+            let borrowed = XdgDesktopFileRef::parse(&contents).unwrap();
+            let names: Vec<&str> = borrowed.sections().map(|(name, _)| name).collect();
+            let owned_names: Vec<&str> = owned.sections().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, owned_names);
+            for (name, keys) in borrowed.sections() {
+                for (key, raw) in keys {
+                    assert_eq!(owned.raw_value(name, key), Some(*raw));
+                    assert!(borrowed.value(name, key).unwrap().is_ok());
+                }
+            }
+        }
+        assert!(XdgDesktopFileRef::parse("Name=Orphan\n").is_err());
+        let invalid =
+            XdgDesktopFileRef::parse("[Desktop Entry]\nName=Foo\n[Bad]Name]\nName=Bar\n").unwrap();
+        assert_eq!(invalid.sections().count(), 1);
+        assert_eq!(invalid.raw_value("Desktop Entry", "Name"), Some("Foo"));
+        let repeated = "[Desktop Entry]\nName=Foo\n[Desktop Entry]\nExec=foo\n";
+        let merged = XdgDesktopFileRef::parse(repeated).unwrap();
+        let owned: XdgDesktopFile = repeated.parse().unwrap();
+        assert_eq!(merged.sections().count(), 1);
+        for key in ["Name", "Exec"] {
+            assert_eq!(
+                merged.raw_value("Desktop Entry", key),
+                owned.raw_value("Desktop Entry", key)
+            );
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    xdg_desktop_value::XdgDesktopValue, xdg_key_file::locale_variants,
+    xdg_parse_error::XdgParseError, xdg_standard_key::StandardKey,
+};
+use indexmap::IndexMap;
+
+/// The keys of a section, in the order they appear in the source file. Values that failed to
+/// parse are kept out of the way in [`diagnostics`](XdgDesktopSection::diagnostics).
+#[derive(Debug, Clone, Default)]
+pub struct XdgDesktopSection {
+    values: IndexMap<String, XdgDesktopValue>,
+    /// The unparsed text of every key's value, including those that failed to parse.
+    raw: IndexMap<String, String>,
+    diagnostics: IndexMap<String, XdgParseError>,
+}
+
+impl XdgDesktopSection {
+    pub fn new() -> XdgDesktopSection {
+        XdgDesktopSection::default()
+    }
+
+    /// Looks up the value of `key`. Returns `None` if the key is missing or its value failed
+    /// to parse.
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&XdgDesktopValue> {
+        self.values.get(key.as_ref())
+    }
+
+    /// Looks up a string, localestring or iconstring value.
+    pub fn get_str(&self, key: impl AsRef<str>) -> Option<&str> {
+        self.get(key)?.string_value()
+    }
+
+    pub fn get_bool(&self, key: impl AsRef<str>) -> Option<bool> {
+        match self.get(key)? {
+            XdgDesktopValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Looks up a numeric value, converting integers to floating point.
+    pub fn get_numeric(&self, key: impl AsRef<str>) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    pub fn get_list(&self, key: impl AsRef<str>) -> Option<&[XdgDesktopValue]> {
+        match self.get(key)? {
+            XdgDesktopValue::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Looks up the translation of `key` best matching `locale`, given in the
+    /// `lang_COUNTRY.ENCODING@MODIFIER` form of `$LC_MESSAGES`, falling back to the
+    /// untranslated value as the specification describes. For `sr_YU@Latn`, `key[sr_YU@Latn]`,
+    /// `key[sr_YU]`, `key[sr@Latn]`, `key[sr]` and `key` are tried in that order.
+    pub fn get_localized(&self, key: impl AsRef<str>, locale: &str) -> Option<&XdgDesktopValue> {
+        let key = key.as_ref();
+        locale_variants(locale)
+            .iter()
+            .find_map(|l| self.get(format!("{}[{}]", key, l)))
+            .or_else(|| self.get(key))
+    }
+
+    /// The text of `key`'s value as it appeared in the source file, before unescaping or
+    /// type conversion. This is available even for values that failed to parse.
+    pub fn raw_value(&self, key: impl AsRef<str>) -> Option<&str> {
+        self.raw.get(key.as_ref()).map(String::as_str)
+    }
+
+    /// Iterates over the keys whose values parsed successfully, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &XdgDesktopValue)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+        self.values.contains_key(key.as_ref())
+    }
+
+    /// The keys whose values failed to parse, with the reason.
+    pub fn diagnostics(&self) -> impl Iterator<Item = (&str, &XdgParseError)> {
+        self.diagnostics.iter().map(|(k, e)| (k.as_str(), e))
+    }
+
+    /// Whether `key` appeared at all, whether or not its value parsed.
+    pub(crate) fn has_key(&self, key: &str) -> bool {
+        self.values.contains_key(key) || self.diagnostics.contains_key(key)
+    }
+
+    /// Records the outcome of parsing `key`'s value, replacing any earlier one. `raw` is the
+    /// value's text, if the line had one.
+    pub(crate) fn insert_parsed(
+        &mut self,
+        key: &str,
+        value: crate::Result<XdgDesktopValue>,
+        raw: Option<&str>,
+    ) {
+        match raw {
+            Some(raw) => {
+                self.raw.insert(key.to_string(), raw.to_string());
+            }
+            None => {
+                self.raw.shift_remove(key);
+            }
+        }
+        match value {
+            Ok(v) => {
+                self.diagnostics.shift_remove(key);
+                self.values.insert(key.to_string(), v);
+            }
+            Err(e) => {
+                self.values.shift_remove(key);
+                self.diagnostics.insert(key.to_string(), e);
+            }
+        }
+    }
+
+    /// Sets `key` to `value`, returning whether the key is new.
+    pub(crate) fn set(&mut self, key: &str, value: XdgDesktopValue) -> bool {
+        let is_new = !self.has_key(key);
+        let raw = value.to_string();
+        self.insert_parsed(key, Ok(value), Some(&raw));
+        is_new
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<XdgDesktopValue> {
+        self.raw.shift_remove(key);
+        self.diagnostics.shift_remove(key);
+        self.values.shift_remove(key)
+    }
+
+    /// Puts the keys in canonical order: those the specification defines in the order it
+    /// lists them, then any others in their current order, each followed by its translations
+    /// sorted by locale. The text of every value that parsed is regenerated from it.
+    pub(crate) fn normalize(&mut self) {
+        let mut bases: Vec<String> = Vec::new();
+        for key in self.raw.keys() {
+            let base = XdgDesktopValue::strip_locale(key);
+            if !bases.contains(&base) {
+                bases.push(base);
+            }
+        }
+        let order = |key: &String| {
+            let base = XdgDesktopValue::strip_locale(key);
+            let rank = match base.parse::<StandardKey>() {
+                Ok(k) => StandardKey::ALL.iter().position(|&s| s == k),
+                Err(_) => bases
+                    .iter()
+                    .position(|b| *b == base)
+                    .map(|i| StandardKey::ALL.len() + i),
+            };
+            (rank, *key != base, key.clone())
+        };
+        self.raw.sort_by_cached_key(|k, _| order(k));
+        self.values.sort_by_cached_key(|k, _| order(k));
+        self.diagnostics.sort_by_cached_key(|k, _| order(k));
+        for (k, v) in &self.values {
+            self.raw.insert(k.clone(), v.to_string());
+        }
+    }
+
+    /// Iterates over every key with a value, in order, with its parsed value if it has one
+    /// and its raw text otherwise.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, Result<&XdgDesktopValue, &str>)> {
+        self.raw.iter().map(move |(k, raw)| {
+            let value = self.values.get(k).ok_or(raw.as_str());
+            (k.as_str(), value)
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a XdgDesktopSection {
+    type Item = (&'a str, &'a XdgDesktopValue);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a XdgDesktopValue)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_lookup() {
+        let mut section = XdgDesktopSection::new();
+        for (k, v) in [
+            ("Name", "Files"),
+            ("Name[de]", "Dateien"),
+            ("Name[sr@Latn]", "Datoteke"),
+        ] {
+            section.set(k, XdgDesktopValue::LocaleString(v.to_string()));
+        }
+        let localized = |locale| section.get_localized("Name", locale).unwrap().to_string();
+        assert_eq!(localized("de_AT.UTF-8"), "Dateien");
+        assert_eq!(localized("sr_RS@Latn"), "Datoteke");
+        assert_eq!(localized("fr_FR"), "Files");
+        assert_eq!(localized(""), "Files");
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut section = XdgDesktopSection::new();
+        section.insert_parsed(
+            "Terminal",
+            "maybe".parse::<bool>().map(Into::into).map_err(Into::into),
+            Some("maybe"),
+        );
+        assert!(section.get("Terminal").is_none());
+        assert_eq!(section.raw_value("Terminal"), Some("maybe"));
+        assert_eq!(section.diagnostics().count(), 1);
+        section.insert_parsed("Terminal", Ok(true.into()), Some("true"));
+        assert_eq!(section.get_bool("Terminal"), Some(true));
+        assert_eq!(section.diagnostics().count(), 0);
+    }
+}
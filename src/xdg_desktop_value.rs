@@ -1,15 +1,30 @@
-use crate::xdg_parse_error::XdgParseError;
-use lazy_static::lazy_static;
-use onig::Regex;
-use std::str;
+use crate::{
+    xdg_key_file::{split_list, unescape, unescape_list_item},
+    xdg_parse_error::XdgParseError,
+    xdg_parser_options::{ParserOptions, UnknownKeys},
+    xdg_standard_key::StandardKey,
+};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    str,
+};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XdgDesktopValue {
     String(String),
     LocaleString(String),
     IconString(String),
     Bool(bool),
     Numeric(f64),
+    /// A numeric value written without a decimal point or exponent, as values of keys of
+    /// unknown type are read with [`UnknownKeys::Guess`]. Keys typed
+    /// [`Numeric`](KeyType::Numeric) are always read as `Numeric`.
+    Integer(i64),
     List(Vec<XdgDesktopValue>),
 }
 
@@ -25,146 +40,472 @@ impl From<f64> for XdgDesktopValue {
     }
 }
 
+impl From<i64> for XdgDesktopValue {
+    fn from(n: i64) -> Self {
+        XdgDesktopValue::Integer(n)
+    }
+}
+
 impl From<Vec<XdgDesktopValue>> for XdgDesktopValue {
     fn from(l: Vec<XdgDesktopValue>) -> Self {
         XdgDesktopValue::List(l)
     }
 }
 
-impl Into<String> for XdgDesktopValue {
-    fn into(self) -> String {
+impl From<XdgDesktopValue> for String {
+    fn from(v: XdgDesktopValue) -> Self {
+        match v {
+            XdgDesktopValue::IconString(s)
+            | XdgDesktopValue::LocaleString(s)
+            | XdgDesktopValue::String(s)
+                if !s.contains(['\\', '\n', '\t', '\r']) =>
+            {
+                s
+            }
+            v => v.to_string(),
+        }
+    }
+}
+
+/// Writes `s`, the text of a string value, with backslashes, newlines, tabs and carriage
+/// returns escaped, so that parsing it gives back `s`. If `s` is a list item, its `;` are
+/// escaped too.
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str, list_item: bool) -> fmt::Result {
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\t' => "\\t",
+            '\r' => "\\r",
+            ';' if list_item => "\\;",
+            _ => continue,
+        };
+        f.write_str(&s[start..i])?;
+        f.write_str(escaped)?;
+        start = i + c.len_utf8();
+    }
+    f.write_str(&s[start..])
+}
+
+/// Writes a numeric value as the specification expects, whatever the user's locale: with a
+/// `.` as the decimal separator, and whole numbers without one. Only magnitudes too large or
+/// small to write out sensibly use an exponent. The shortest text that parses back to `n` is
+/// used.
+fn write_numeric(f: &mut fmt::Formatter<'_>, n: f64) -> fmt::Result {
+    if !n.is_finite() || n == 0.0 || (1e-5..1e16).contains(&n.abs()) {
+        write!(f, "{}", n)
+    } else {
+        write!(f, "{:e}", n)
+    }
+}
+
+impl XdgDesktopValue {
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>, list_item: bool) -> fmt::Result {
         match self {
             XdgDesktopValue::IconString(s)
             | XdgDesktopValue::LocaleString(s)
-            | XdgDesktopValue::String(s) => s.clone(), // I wish I didn't have to clone here
-            XdgDesktopValue::Bool(b) => b.to_string(),
-            XdgDesktopValue::Numeric(n) => n.to_string(),
+            | XdgDesktopValue::String(s) => write_escaped(f, s, list_item),
+            XdgDesktopValue::Bool(b) => write!(f, "{}", b),
+            XdgDesktopValue::Numeric(n) => write_numeric(f, *n),
+            XdgDesktopValue::Integer(n) => write!(f, "{}", n),
             XdgDesktopValue::List(l) => {
-                // Arbitrary size chosen
-                let mut out = String::with_capacity(8 * l.len());
-                for e in l.iter().map(XdgDesktopValue::to_string) {
-                    out.push_str(&e);
-                    out.push(';')
+                for e in l {
+                    e.fmt_value(f, true)?;
+                    f.write_str(";")?;
                 }
-                out
+                Ok(())
             }
         }
     }
 }
 
-impl std::fmt::Display for XdgDesktopValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+impl fmt::Display for XdgDesktopValue {
+    /// Writes the value as it would appear in a file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_value(f, false)
+    }
+}
+
+/// Values are equal if they are of the same variant and hold equal contents; an `Integer` is
+/// never equal to a `Numeric`, nor a `String` to a `LocaleString`. Floating-point values are
+/// compared exactly, with `NaN` equal to itself so that the comparison is an equivalence
+/// relation; see [`approx_eq`](XdgDesktopValue::approx_eq) for a tolerant comparison.
+impl PartialEq for XdgDesktopValue {
+    fn eq(&self, other: &XdgDesktopValue) -> bool {
+        use XdgDesktopValue::*;
+        match (self, other) {
+            (String(a), String(b)) | (LocaleString(a), LocaleString(b)) => a == b,
+            (IconString(a), IconString(b)) => a == b,
+            (Bool(a), Bool(b)) => a == b,
+            (Numeric(a), Numeric(b)) => a.total_cmp(b) == Ordering::Equal,
+            (Integer(a), Integer(b)) => a == b,
+            (List(a), List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for XdgDesktopValue {}
+
+impl Hash for XdgDesktopValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            XdgDesktopValue::String(s)
+            | XdgDesktopValue::LocaleString(s)
+            | XdgDesktopValue::IconString(s) => s.hash(state),
+            XdgDesktopValue::Bool(b) => b.hash(state),
+            XdgDesktopValue::Numeric(n) => n.to_bits().hash(state),
+            XdgDesktopValue::Integer(n) => n.hash(state),
+            XdgDesktopValue::List(l) => l.hash(state),
+        }
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for String {
+    type Error = XdgParseError;
+
+    /// Converts a string, localestring or iconstring value.
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<String> {
+        v.string_value()
+            .map(str::to_string)
+            .ok_or_else(|| v.mismatch("string"))
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for bool {
+    type Error = XdgParseError;
+
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<bool> {
+        match v {
+            XdgDesktopValue::Bool(b) => Ok(*b),
+            _ => Err(v.mismatch("boolean")),
+        }
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for f64 {
+    type Error = XdgParseError;
+
+    /// Converts a numeric value, integer or not.
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<f64> {
+        v.as_f64().ok_or_else(|| v.mismatch("numeric"))
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for i64 {
+    type Error = XdgParseError;
+
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<i64> {
+        v.as_i64().ok_or_else(|| v.mismatch("integer"))
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for Vec<String> {
+    type Error = XdgParseError;
+
+    /// Converts a list of strings, localestrings or iconstrings.
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<Vec<String>> {
+        match v {
+            XdgDesktopValue::List(l) => l.iter().map(String::try_from).collect(),
+            _ => Err(v.mismatch("list")),
+        }
+    }
+}
+
+impl TryFrom<&XdgDesktopValue> for PathBuf {
+    type Error = XdgParseError;
+
+    /// Converts a string value, such as that of `Path` or `TryExec`.
+    fn try_from(v: &XdgDesktopValue) -> crate::Result<PathBuf> {
+        String::try_from(v).map(PathBuf::from)
     }
 }
 
-lazy_static! {
-    static ref VAL_DELIMITER: Regex = Regex::new(r#"(?<!\\);"#).unwrap();
+/// The length of the locale suffix (e.g. `[sr_RS@latin]`) at the start of `s`, if any.
+fn locale_suffix_len(s: &str) -> Option<usize> {
+    let rest = s.strip_prefix('[')?;
+    let b = rest.as_bytes();
+    if !b.get(..2)?.iter().all(u8::is_ascii_lowercase) {
+        return None;
+    }
+    let mut i = 2;
+    let country = b.get(i + 1..i + 3);
+    if b.get(i) == Some(&b'_') && country.is_some_and(|c| c.iter().all(u8::is_ascii_uppercase)) {
+        i += 3;
+    }
+    if b.get(i) == Some(&b'@') {
+        let modifier = &rest[i + 1..];
+        let len = modifier
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(modifier.len());
+        if len == 0 {
+            return None;
+        }
+        i += 1 + len;
+    }
+    match b.get(i) {
+        Some(b']') => Some(i + 2),
+        _ => None,
+    }
 }
 
 impl XdgDesktopValue {
+    /// The contents of a string, localestring or iconstring value.
+    pub(crate) fn string_value(&self) -> Option<&str> {
+        match self {
+            XdgDesktopValue::String(s)
+            | XdgDesktopValue::LocaleString(s)
+            | XdgDesktopValue::IconString(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The name of the value's type, as the specification calls it.
+    fn type_name(&self) -> &'static str {
+        match self {
+            XdgDesktopValue::String(_) => "string",
+            XdgDesktopValue::LocaleString(_) => "localestring",
+            XdgDesktopValue::IconString(_) => "iconstring",
+            XdgDesktopValue::Bool(_) => "boolean",
+            XdgDesktopValue::Numeric(_) => "numeric",
+            XdgDesktopValue::Integer(_) => "integer",
+            XdgDesktopValue::List(_) => "list",
+        }
+    }
+
+    fn mismatch(&self, expected: &'static str) -> XdgParseError {
+        XdgParseError::TypeMismatch {
+            expected,
+            found: self.type_name(),
+        }
+    }
+
+    /// The value of a numeric key, integer or not.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            XdgDesktopValue::Numeric(n) => Some(*n),
+            XdgDesktopValue::Integer(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer-valued numeric key, whether read as an `Integer` or as a
+    /// whole `Numeric` that fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            XdgDesktopValue::Integer(n) => Some(*n),
+            XdgDesktopValue::Numeric(n)
+                if n.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(n) =>
+            {
+                Some(*n as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Like `==`, but numeric values (including integers) are equal if they differ by at most
+    /// `tolerance`. Lists are compared element-wise.
+    pub fn approx_eq(&self, other: &XdgDesktopValue, tolerance: f64) -> bool {
+        match (self, other) {
+            (XdgDesktopValue::List(a), XdgDesktopValue::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, tolerance))
+            }
+            (a, b) => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= tolerance,
+                _ => a == b,
+            },
+        }
+    }
+
     fn parse_string(s: &str) -> crate::Result<XdgDesktopValue> {
-        Ok(XdgDesktopValue::String(s.to_string()))
+        Ok(XdgDesktopValue::String(unescape(s)))
     }
 
     fn parse_locale_string(s: &str) -> crate::Result<XdgDesktopValue> {
-        Ok(XdgDesktopValue::LocaleString(s.to_string()))
+        Ok(XdgDesktopValue::LocaleString(unescape(s)))
     }
 
     fn parse_icon_string(s: &str) -> crate::Result<XdgDesktopValue> {
-        Ok(XdgDesktopValue::IconString(s.to_string()))
+        Ok(XdgDesktopValue::IconString(unescape(s)))
     }
 
     fn parse_bool(s: &str) -> crate::Result<XdgDesktopValue> {
         Ok(s.parse::<bool>()?.into())
     }
 
+    /// Parses a boolean, also accepting the legacy `0` and `1` and any capitalization.
+    fn parse_legacy_bool(s: &str) -> crate::Result<XdgDesktopValue> {
+        match s {
+            "1" => Ok(true.into()),
+            "0" => Ok(false.into()),
+            s if s.eq_ignore_ascii_case("true") => Ok(true.into()),
+            s if s.eq_ignore_ascii_case("false") => Ok(false.into()),
+            s => XdgDesktopValue::parse_bool(s),
+        }
+    }
+
+    /// Parses a number, as an integer if it has no decimal point or exponent and fits.
     fn parse_numeric(s: &str) -> crate::Result<XdgDesktopValue> {
+        if let Ok(n) = s.parse::<i64>() {
+            return Ok(n.into());
+        }
+        XdgDesktopValue::parse_float(s)
+    }
+
+    /// Parses a number as `Numeric`, even if it is written as an integer, so that every
+    /// `Numeric` reads back as itself.
+    fn parse_float(s: &str) -> crate::Result<XdgDesktopValue> {
         Ok(s.parse::<f64>()?.into())
     }
 
-    fn parse_plural(
-        s: &str,
-        f: fn(&str) -> crate::Result<XdgDesktopValue>,
-    ) -> crate::Result<XdgDesktopValue> {
-        let items: Result<Vec<XdgDesktopValue>, _> = VAL_DELIMITER.split(s).map(f).collect();
-        Ok(XdgDesktopValue::List(items?))
+    /// Parses a list of strings, making each item, with its escape sequences expanded, a
+    /// value with `f`.
+    fn parse_plural(s: &str, f: fn(String) -> XdgDesktopValue) -> crate::Result<XdgDesktopValue> {
+        Ok(XdgDesktopValue::List(
+            split_list(s).map(|v| f(unescape_list_item(v))).collect(),
+        ))
     }
 
-    fn strip_locale(s: &str) -> String {
-        lazy_static! {
-            static ref LOCALE_SUFFIX: Regex =
-                Regex::new(r#"\[(?:[a-z]{2})(?:_[A-Z]{2})?(?:@\w+)?\]"#).unwrap();
+    /// Removes the first locale suffix from a key.
+    pub(crate) fn strip_locale(s: &str) -> String {
+        for (i, _) in s.match_indices('[') {
+            if let Some(len) = locale_suffix_len(&s[i..]) {
+                return format!("{}{}", &s[..i], &s[i + len..]);
+            }
         }
-        LOCALE_SUFFIX.replace(s, "")
+        s.to_string()
     }
 
+    /// Parses the value of a key of unknown type as the most specific type it fits. Values
+    /// containing an unescaped `;` are lists, whose items all get the most specific type that
+    /// every one of them fits; anything else is a single value.
     fn try_types(s: &str) -> crate::Result<XdgDesktopValue> {
+        // Each is given text whose escape sequences are already expanded.
         const PARSE_FUNCS: [fn(&str) -> crate::Result<XdgDesktopValue>; 3] = [
             XdgDesktopValue::parse_bool,
             XdgDesktopValue::parse_numeric,
-            XdgDesktopValue::parse_string,
+            |s| Ok(XdgDesktopValue::String(s.to_string())),
         ];
-        let mut parse_fn: Option<fn(&str) -> crate::Result<XdgDesktopValue>> = None;
-        let mut out: Vec<XdgDesktopValue> = Vec::new();
-        'outer: for v in VAL_DELIMITER.split(s) {
-            match parse_fn {
-                Some(f) => out.push(f(v)?),
-                None => {
-                    for f in &PARSE_FUNCS {
-                        if let Ok(val) = f(s) {
-                            out.push(val);
-                            parse_fn = Some(*f);
-                            continue 'outer;
-                        }
-                    }
-                    // parse_string cannot fail.
-                    unreachable!()
-                }
-            }
-        }
-        Ok(XdgDesktopValue::List(out))
+        let items: Vec<&str> = split_list(s).collect();
+        // The first item only falls short of the whole value if there is a separator.
+        let is_list = items.first().is_some_and(|first| first.len() < s.len());
+        let parsed = if is_list {
+            let unescaped: Vec<String> = items.iter().map(|v| unescape_list_item(v)).collect();
+            PARSE_FUNCS
+                .iter()
+                .find_map(|f| {
+                    unescaped
+                        .iter()
+                        .map(|v| f(v))
+                        .collect::<crate::Result<_>>()
+                        .ok()
+                })
+                .map(XdgDesktopValue::List)
+        } else {
+            let unescaped = unescape(s);
+            PARSE_FUNCS.iter().find_map(|f| f(&unescaped).ok())
+        };
+        parsed.ok_or(XdgParseError::Other("Value matches no type"))
     }
 
     pub fn from_kv(s: &str) -> (&str, crate::Result<XdgDesktopValue>) {
-        let parse_strings =
-            |s: &str| XdgDesktopValue::parse_plural(s, XdgDesktopValue::parse_string);
-        let parse_locale_strings =
-            |s: &str| XdgDesktopValue::parse_plural(s, XdgDesktopValue::parse_locale_string);
+        XdgDesktopValue::from_kv_with_options(s, &ParserOptions::default())
+    }
+
+    pub(crate) fn from_kv_with_options<'a>(
+        s: &'a str,
+        options: &ParserOptions,
+    ) -> (&'a str, crate::Result<XdgDesktopValue>) {
         let (k, v) = match s.split_once('=') {
-            Some(tpl) => tpl,
+            Some((k, v)) => (k.trim_end(), v.trim_start()),
             None => return (s, Err(XdgParseError::Other("No delimiter found in line"))),
         };
+        (k, XdgDesktopValue::from_key_value(k, v, options))
+    }
+
+    /// Parses the value `v` of the key `k`, which may carry a locale suffix.
+    pub(crate) fn from_key_value(
+        k: &str,
+        v: &str,
+        options: &ParserOptions,
+    ) -> crate::Result<XdgDesktopValue> {
         let key_base = XdgDesktopValue::strip_locale(k);
-        #[rustfmt::skip]
-            let parse_fn = match key_base.as_ref() {
-            "Type"
-            | "Version"
-            | "Exec"
-            | "TryExec"
-            | "Path"
-            | "StartupWMClass"
-            | "URL" => XdgDesktopValue::parse_string,
-            "Name" | "GenericName" | "Comment" => XdgDesktopValue::parse_locale_string,
-            "NoDisplay"
-            | "Hidden"
-            | "Terminal"
-            | "StartupNotify"
-            | "PrefersNonDefaultGPU"
-            | "DBusActivatable" => XdgDesktopValue::parse_bool,
-            "Icon" => XdgDesktopValue::parse_icon_string,
-            "Keywords" => parse_locale_strings,
-            "OnlyShowIn"
-            | "NotShowIn"
-            | "Actions"
-            | "MimeType"
-            | "Categories"
-            | "Implements" => parse_strings,
-            _ => XdgDesktopValue::try_types
+        if SPEC_STRINGS.contains(&key_base.as_str()) {
+            return XdgDesktopValue::parse_string(v);
+        }
+        let key_type = options.key_type(&key_base);
+        let key_type = match (key_type, &options.unknown_keys) {
+            (Some(key_type), _) => Some(key_type),
+            (None, UnknownKeys::Raw) => Some(KeyType::String),
+            (None, UnknownKeys::Guess) => None,
+            (None, UnknownKeys::Custom(f)) => Some(f(&key_base, v)),
+        };
+        let parse_fn = match key_type {
+            Some(KeyType::Bool) if options.legacy_booleans => XdgDesktopValue::parse_legacy_bool,
+            Some(key_type) => key_type.parse_fn(),
+            None => XdgDesktopValue::try_types,
         };
-        match parse_fn(v) {
-            Ok(xdg) => (k, Ok(xdg)),
-            Err(e) => (k, Err(e)),
+        parse_fn(v)
+    }
+}
+
+/// Keys whose values look numeric but are strings, which must round-trip textually (`1.10`
+/// isn't `1.1`). These are always parsed as strings, ahead of any key table or
+/// [`UnknownKeys`] policy.
+const SPEC_STRINGS: [&str; 2] = ["Version", "X-Desktop-File-Install-Version"];
+
+/// Whether `key`, less any locale suffix, consists only of the characters the specification
+/// allows: `A-Za-z0-9-`.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    let base = key.split_once('[').map_or(key, |(base, _)| base);
+    !base.is_empty() && base.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+/// Whether `key` is a valid key with at most a well-formed locale suffix at its end, and so
+/// can be written to a file without changing its meaning.
+pub(crate) fn is_writable_key(key: &str) -> bool {
+    let suffix = key.find('[').map_or("", |i| &key[i..]);
+    is_valid_key(key) && (suffix.is_empty() || locale_suffix_len(suffix) == Some(suffix.len()))
+}
+
+/// Whether `key` either has no locale suffix or a well-formed one.
+pub(crate) fn has_valid_locale(key: &str) -> bool {
+    !XdgDesktopValue::strip_locale(key).contains(['[', ']'])
+}
+
+/// The type of a key's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    String,
+    LocaleString,
+    IconString,
+    Bool,
+    Numeric,
+    /// A list of strings.
+    Strings,
+    /// A list of localestrings.
+    LocaleStrings,
+}
+
+impl KeyType {
+    /// Returns the type of a key defined by the specification, or `None` for any other key.
+    pub fn of_standard_key(key: &str) -> Option<KeyType> {
+        key.parse::<StandardKey>().ok().map(StandardKey::key_type)
+    }
+
+    fn parse_fn(self) -> fn(&str) -> crate::Result<XdgDesktopValue> {
+        match self {
+            KeyType::String => XdgDesktopValue::parse_string,
+            KeyType::LocaleString => XdgDesktopValue::parse_locale_string,
+            KeyType::IconString => XdgDesktopValue::parse_icon_string,
+            KeyType::Bool => XdgDesktopValue::parse_bool,
+            KeyType::Numeric => XdgDesktopValue::parse_float,
+            KeyType::Strings => |s| XdgDesktopValue::parse_plural(s, XdgDesktopValue::String),
+            KeyType::LocaleStrings => {
+                |s| XdgDesktopValue::parse_plural(s, XdgDesktopValue::LocaleString)
+            }
         }
     }
 }
@@ -172,6 +513,7 @@ impl XdgDesktopValue {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_localize_strip() {
@@ -181,9 +523,302 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_split_list() {
+        let split = |s| split_list(s).collect::<Vec<_>>();
+        assert_eq!(split("a;b\\;c;"), ["a", "b\\;c"]);
+        assert_eq!(split("a;;"), ["a", ""]);
+        assert_eq!(split(";"), [""]);
+        assert!(split("").is_empty());
+        assert_eq!(XdgDesktopValue::strip_locale("Name[de][fr]"), "Name[fr]");
+        assert_eq!(XdgDesktopValue::strip_locale("Name[D]"), "Name[D]");
+    }
+
     #[test]
     fn test_list() {
         let input = "Keywords=system;process;task";
         assert!(XdgDesktopValue::from_kv(input).1.is_ok())
     }
+
+    #[test]
+    fn test_equality() {
+        use std::collections::HashSet;
+        use XdgDesktopValue::*;
+        assert_eq!(String("a".into()), String("a".into()));
+        assert_ne!(String("a".into()), LocaleString("a".into()));
+        assert_ne!(Integer(1), Numeric(1.0));
+        assert_eq!(Numeric(f64::NAN), Numeric(f64::NAN));
+        let list = |items: &[f64]| List(items.iter().map(|&n| Numeric(n)).collect());
+        assert_eq!(list(&[1.0, 2.0]), list(&[1.0, 2.0]));
+        assert_ne!(list(&[1.0, 2.0]), list(&[1.0]));
+        assert!(list(&[0.1 + 0.2]).approx_eq(&list(&[0.3]), 1e-9));
+        assert!(!list(&[0.1 + 0.2]).approx_eq(&list(&[0.3]), 0.0));
+        assert!(Integer(1).approx_eq(&Numeric(1.0), 0.0));
+        let set: HashSet<XdgDesktopValue> = [Bool(true), Bool(true), Integer(2)].into();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_conversions() {
+        use std::convert::TryInto;
+        let keywords = XdgDesktopValue::from_kv("Keywords=system;process;")
+            .1
+            .unwrap();
+        let keywords: Vec<String> = (&keywords).try_into().unwrap();
+        assert_eq!(keywords, ["system", "process"]);
+        let exec: String = (&XdgDesktopValue::String("foo %U".into()))
+            .try_into()
+            .unwrap();
+        assert_eq!(exec, "foo %U");
+        let path: PathBuf = (&XdgDesktopValue::String("/opt".into()))
+            .try_into()
+            .unwrap();
+        assert_eq!(path, PathBuf::from("/opt"));
+        assert_eq!(f64::try_from(&XdgDesktopValue::Integer(2)).unwrap(), 2.0);
+        assert!(bool::try_from(&XdgDesktopValue::Bool(true)).unwrap());
+        match bool::try_from(&XdgDesktopValue::List(Vec::new())) {
+            Err(XdgParseError::TypeMismatch { expected, found }) => {
+                assert_eq!((expected, found), ("boolean", "list"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        let mixed = XdgDesktopValue::List(vec![true.into()]);
+        assert!(Vec::<String>::try_from(&mixed).is_err());
+    }
+
+    #[test]
+    fn test_untyped_list() {
+        use XdgDesktopValue::*;
+        let items = |s| match XdgDesktopValue::from_kv(s).1 {
+            Ok(List(l)) => l,
+            other => panic!("unexpected value {:?}", other),
+        };
+        assert!(matches!(
+            items("X-Flags=true;false;")[..],
+            [Bool(true), Bool(false)]
+        ));
+        assert!(matches!(
+            items("X-Sizes=16;1.5;")[..],
+            [Integer(16), Numeric(_)]
+        ));
+        // Items are only typed if all of them parse as that type.
+        let mixed = items("X-Mixed=1;foo;");
+        assert!(matches!(&mixed[..], [String(a), String(b)] if a == "1" && b == "foo"));
+        assert!(matches!(
+            &items("X-Bar=a;b;c;")[..],
+            [String(_), String(_), String(_)]
+        ));
+        assert!(matches!(&items("X-One=a;")[..], [String(a)] if a == "a"));
+    }
+
+    #[test]
+    fn test_display_escaping() {
+        use XdgDesktopValue::*;
+        let list = |items: &[&str]| List(items.iter().map(|s| String(s.to_string())).collect());
+        assert_eq!(list(&["a;b", "c"]).to_string(), "a\\;b;c;");
+        assert_eq!(list(&["a\\", "b"]).to_string(), "a\\\\;b;");
+        assert_eq!(list(&["a\\\\", "b"]).to_string(), "a\\\\\\\\;b;");
+        assert_eq!(list(&["line\nbreak"]).to_string(), "line\\nbreak;");
+        assert_eq!(String("tab\there".into()).to_string(), "tab\\there");
+        assert_eq!(String("a;b\\s".into()).to_string(), "a;b\\\\s");
+        assert_eq!(std::string::String::from(String("plain".into())), "plain");
+        assert_eq!(
+            std::string::String::from(LocaleString("two\nlines".into())),
+            "two\\nlines"
+        );
+        assert_eq!(
+            std::string::String::from(String("C:\\dir".into())),
+            "C:\\\\dir"
+        );
+        assert_eq!(List(vec![Integer(1), Bool(true)]).to_string(), "1;true;");
+        for input in ["Keywords=a\\;b;c\\\\;d;", "X-Foo=x\\;y;z;"] {
+            let (key, value) = XdgDesktopValue::from_kv(input);
+            assert_eq!(format!("{}={}", key, value.unwrap()), input);
+        }
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        use XdgDesktopValue::*;
+        let values = [
+            String("C:\\new\nline\ttab\r".into()),
+            String("\\n is not a newline".into()),
+            LocaleString("a;b\\".into()),
+            List(vec![String("a\\;b".into()), String("c\n".into())]),
+        ];
+        for (key, value) in ["Path", "TryExec", "Comment", "MimeType"]
+            .iter()
+            .zip(values)
+        {
+            let input = format!("{}={}", key, value);
+            let (_, parsed) = XdgDesktopValue::from_kv(&input);
+            assert_eq!(parsed.unwrap(), value, "{}", input);
+        }
+        let (_, v) = XdgDesktopValue::from_kv("Comment=a\\sb\\\\c\\nd");
+        assert_eq!(v.unwrap(), LocaleString("a b\\c\nd".into()));
+    }
+
+    #[test]
+    fn test_numeric_formatting() {
+        let text = |n: f64| XdgDesktopValue::Numeric(n).to_string();
+        assert_eq!(text(2.0), "2");
+        assert_eq!(text(-0.0), "-0");
+        assert_eq!(text(1e15), "1000000000000000");
+        assert_eq!(text(-0.5), "-0.5");
+        assert_eq!(text(1.1), "1.1");
+        assert_eq!(text(123456.789), "123456.789");
+        assert_eq!(text(1e20), "1e20");
+        assert_eq!(text(-2.5e-7), "-2.5e-7");
+        assert_eq!(text(f64::INFINITY), "inf");
+    }
+
+    proptest! {
+        #[test]
+        fn test_numeric_round_trip(n in any::<f64>()) {
+            let options = ParserOptions::new().with_key_type("X-Value", KeyType::Numeric);
+            let input = format!("X-Value={}", XdgDesktopValue::Numeric(n));
+            let (_, v) = XdgDesktopValue::from_kv_with_options(&input, &options);
+            match v.unwrap() {
+                XdgDesktopValue::Numeric(parsed) => prop_assert!(
+                    parsed.to_bits() == n.to_bits() || (parsed.is_nan() && n.is_nan()),
+                    "{}",
+                    input
+                ),
+                other => prop_assert!(false, "{} parsed as {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_integral_numeric_round_trip() {
+        let options = ParserOptions::new().with_key_type("X-Value", KeyType::Numeric);
+        for n in [2.0, -0.0, 0.0, 1e15, 1e20, -3.0] {
+            let input = format!("X-Value={}", XdgDesktopValue::Numeric(n));
+            assert!(!input.ends_with(".0"), "{}", input);
+            let (_, v) = XdgDesktopValue::from_kv_with_options(&input, &options);
+            assert_eq!(v.unwrap(), XdgDesktopValue::Numeric(n), "{}", input);
+        }
+        // Without a declared type, whole numbers are guessed to be integers.
+        let (_, v) = XdgDesktopValue::from_kv("X-Value=2");
+        assert_eq!(v.unwrap(), XdgDesktopValue::Integer(2));
+    }
+
+    #[test]
+    fn test_list_separators() {
+        let list = |s| match XdgDesktopValue::from_kv(s).1 {
+            Ok(v @ XdgDesktopValue::List(_)) => v,
+            other => panic!("unexpected value {:?}", other),
+        };
+        let strings = |v: &XdgDesktopValue| match v {
+            XdgDesktopValue::List(l) => l.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(strings(&list("Keywords=a;b;")), ["a", "b"]);
+        assert_eq!(strings(&list("Keywords=a;b")), ["a", "b"]);
+        assert_eq!(strings(&list("Keywords=a;;")), ["a", ""]);
+        assert!(strings(&list("Keywords=")).is_empty());
+        let escaped = list("Keywords=a\\;b;c;");
+        assert_eq!(strings(&escaped), ["a;b", "c"]);
+        assert_eq!(escaped.to_string(), "a\\;b;c;");
+        // The backslash before the `;` is itself escaped, so the `;` separates.
+        assert_eq!(strings(&list("Keywords=a\\\\;b;")), ["a\\\\", "b"]);
+        assert_eq!(strings(&list("X-Foo=a\\;b;c;")), ["a;b", "c"]);
+    }
+
+    #[test]
+    fn test_untyped_scalar() {
+        use XdgDesktopValue::*;
+        let value = |s| XdgDesktopValue::from_kv(s).1.unwrap();
+        assert!(matches!(value("X-Foo=3.5"), Numeric(n) if n == 3.5));
+        assert!(matches!(value("X-Count=3"), Integer(3)));
+        assert!(matches!(value("X-Enabled=true"), Bool(true)));
+        assert!(matches!(value(r"X-Name=a\;b"), String(s) if s == r"a\;b"));
+        assert!(matches!(value("X-Empty="), String(s) if s.is_empty()));
+    }
+
+    #[test]
+    fn test_registered_key_type() {
+        let input = "X-GNOME-Autostart-Delay=2";
+        let options =
+            ParserOptions::new().with_key_type("X-GNOME-Autostart-Delay", KeyType::Numeric);
+        let (_, v) = XdgDesktopValue::from_kv_with_options(input, &options);
+        let v = v.unwrap();
+        assert_eq!(v, XdgDesktopValue::Numeric(2.0));
+        assert_eq!(v.as_i64(), Some(2));
+        let options = ParserOptions::new().with_key_type("X-Scale", KeyType::Numeric);
+        let (_, v) = XdgDesktopValue::from_kv_with_options("X-Scale=1.5", &options);
+        let v = v.unwrap();
+        assert_eq!((v.as_f64(), v.as_i64()), (Some(1.5), None));
+        // Standard keys can't be re-typed.
+        let options = ParserOptions::new().with_key_type("Terminal", KeyType::String);
+        let (_, v) = XdgDesktopValue::from_kv_with_options("Terminal=false", &options);
+        assert!(matches!(v, Ok(XdgDesktopValue::Bool(false))));
+    }
+
+    #[test]
+    fn test_version_is_string() {
+        for policy in [
+            UnknownKeys::Guess,
+            UnknownKeys::Raw,
+            UnknownKeys::custom(|_, _| KeyType::Numeric),
+        ] {
+            let options = ParserOptions::new()
+                .unknown_keys(policy)
+                .with_key_type("X-Desktop-File-Install-Version", KeyType::Numeric);
+            for input in ["Version=1.10", "X-Desktop-File-Install-Version=0.10"] {
+                let (_, v) = XdgDesktopValue::from_kv_with_options(input, &options);
+                let v = v.unwrap();
+                assert!(matches!(v, XdgDesktopValue::String(_)), "{}", input);
+                assert_eq!(v.to_string(), input.split_once('=').unwrap().1);
+            }
+            let file = options.parse("[Desktop Entry]\nVersion=1.10\n").unwrap();
+            assert_eq!(file.to_string(), "[Desktop Entry]\nVersion=1.10\n");
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        use XdgDesktopValue::*;
+        let value = |s, policy| {
+            let options = ParserOptions::new()
+                .unknown_keys(policy)
+                .with_key_type("X-Declared", KeyType::Numeric);
+            XdgDesktopValue::from_kv_with_options(s, &options)
+                .1
+                .unwrap()
+        };
+        assert!(matches!(
+            value("X-Version=1.10", UnknownKeys::Guess),
+            Numeric(_)
+        ));
+        assert!(matches!(value("X-Version=1.10", UnknownKeys::Raw), String(s) if s == "1.10"));
+        assert!(matches!(value("X-Flags=a;b;", UnknownKeys::Raw), String(s) if s == "a;b;"));
+        assert!(matches!(
+            value("X-Declared=2", UnknownKeys::Raw),
+            Numeric(n) if n == 2.0
+        ));
+        assert!(matches!(
+            value("Terminal=true", UnknownKeys::Raw),
+            Bool(true)
+        ));
+        // A schema loaded at runtime, captured by the policy.
+        let booleans: std::collections::HashSet<std::string::String> =
+            ["X-Foo-Enabled".to_string()].into();
+        let custom = UnknownKeys::custom(move |key, _| {
+            if booleans.contains(key) {
+                KeyType::Bool
+            } else {
+                KeyType::Strings
+            }
+        });
+        assert!(matches!(
+            value("X-Foo-Enabled[de]=true", custom.clone()),
+            Bool(true)
+        ));
+        assert!(matches!(&value("X-Tags=1;2;", custom.clone()), List(l) if l.len() == 2));
+        assert!(
+            matches!(&value("X-Tags=1;2;", custom.clone()), List(l) if matches!(l[0], String(_)))
+        );
+        assert_eq!(format!("{:?}", custom), "Custom(..)");
+    }
 }
@@ -0,0 +1,167 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_desktop_section::XdgDesktopSection};
+use std::fmt;
+
+/// A difference between two desktop files, as found by [`XdgDesktopFile::diff`]. Values are
+/// given as their text in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffEntry {
+    SectionAdded(String),
+    SectionRemoved(String),
+    KeyAdded {
+        section: String,
+        key: String,
+        value: String,
+    },
+    KeyRemoved {
+        section: String,
+        key: String,
+        value: String,
+    },
+    KeyChanged {
+        section: String,
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffEntry::SectionAdded(s) => write!(f, "+[{}]", s),
+            DiffEntry::SectionRemoved(s) => write!(f, "-[{}]", s),
+            DiffEntry::KeyAdded {
+                section,
+                key,
+                value,
+            } => write!(f, "[{}] +{}={}", section, key, value),
+            DiffEntry::KeyRemoved {
+                section,
+                key,
+                value,
+            } => write!(f, "[{}] -{}={}", section, key, value),
+            DiffEntry::KeyChanged {
+                section,
+                key,
+                old,
+                new,
+            } => write!(f, "[{}] {}: {} -> {}", section, key, old, new),
+        }
+    }
+}
+
+/// Every key of `section` with its text and a normalized form for comparison: the serialized
+/// value if it parsed, so `Terminal=1` and `Terminal=true` are equal, and the raw text if not.
+fn normalized(section: &XdgDesktopSection) -> Vec<(&str, &str, String)> {
+    section
+        .entries()
+        .map(|(k, v)| {
+            let raw = section.raw_value(k).unwrap_or_default();
+            let norm = match v {
+                Ok(v) => v.to_string(),
+                Err(raw) => raw.to_string(),
+            };
+            (k, raw, norm)
+        })
+        .collect()
+}
+
+fn diff_sections(
+    name: &str,
+    old: &XdgDesktopSection,
+    new: &XdgDesktopSection,
+    out: &mut Vec<DiffEntry>,
+) {
+    let (old, new) = (normalized(old), normalized(new));
+    for (key, old_raw, old_norm) in &old {
+        match new.iter().find(|(k, _, _)| k == key) {
+            None => out.push(DiffEntry::KeyRemoved {
+                section: name.to_string(),
+                key: key.to_string(),
+                value: old_raw.to_string(),
+            }),
+            Some((_, new_raw, new_norm)) if new_norm != old_norm => {
+                out.push(DiffEntry::KeyChanged {
+                    section: name.to_string(),
+                    key: key.to_string(),
+                    old: old_raw.to_string(),
+                    new: new_raw.to_string(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, new_raw, _) in &new {
+        if !old.iter().any(|(k, _, _)| k == key) {
+            out.push(DiffEntry::KeyAdded {
+                section: name.to_string(),
+                key: key.to_string(),
+                value: new_raw.to_string(),
+            });
+        }
+    }
+}
+
+impl XdgDesktopFile {
+    /// Lists the differences between this file and `other`: first the sections and keys
+    /// removed or changed, in this file's order, then those added, in `other`'s. An added or
+    /// removed section is followed by its keys. Values are compared after parsing, so
+    /// differences in spelling alone, such as `Terminal=1` against `Terminal=true`, aren't
+    /// reported. Comments and the order of keys are ignored.
+    pub fn diff(&self, other: &XdgDesktopFile) -> Vec<DiffEntry> {
+        let empty = XdgDesktopSection::new();
+        let mut out = Vec::new();
+        for (name, section) in self.sections() {
+            match other.section(name.as_str()) {
+                Some(other_section) => {
+                    diff_sections(name.as_str(), section, other_section, &mut out)
+                }
+                None => {
+                    out.push(DiffEntry::SectionRemoved(name.to_string()));
+                    diff_sections(name.as_str(), section, &empty, &mut out);
+                }
+            }
+        }
+        for (name, section) in other.sections() {
+            if self.section(name.as_str()).is_none() {
+                out.push(DiffEntry::SectionAdded(name.to_string()));
+                diff_sections(name.as_str(), &empty, section, &mut out);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_diff() {
+        let old = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nName=Foo\nExec=foo\nTerminal=1\nIcon=foo\n\
+             [Desktop Action Old]\nName=Old\n",
+        )
+        .unwrap();
+        let new = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nName=Foo\nTerminal=true\nExec=foo --new\nComment=A foo\n\
+             [Desktop Action New]\nName=New\n",
+        )
+        .unwrap();
+        let changes: Vec<String> = old.diff(&new).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "[Desktop Entry] Exec: foo -> foo --new",
+                "[Desktop Entry] -Icon=foo",
+                "[Desktop Entry] +Comment=A foo",
+                "-[Desktop Action Old]",
+                "[Desktop Action Old] -Name=Old",
+                "+[Desktop Action New]",
+                "[Desktop Action New] +Name=New",
+            ]
+        );
+        assert_eq!(new.diff(&new), []);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile, xdg_entry_type::EntryType, xdg_parse_error::XdgParseError,
+};
+use std::{ops::Deref, path::Path, str::FromStr};
+
+/// A `.directory` file, describing a menu or other group of entries. These share the desktop
+/// entry format and are distinguished by `Type=Directory`.
+#[derive(Debug)]
+pub struct XdgDirectoryEntry {
+    file: XdgDesktopFile,
+}
+
+impl XdgDirectoryEntry {
+    /// Wraps `file`, failing unless its `Type` is `Directory`.
+    pub fn new(file: XdgDesktopFile) -> crate::Result<XdgDirectoryEntry> {
+        match file.entry_type() {
+            Some(EntryType::Directory) => Ok(XdgDirectoryEntry { file }),
+            Some(other) => Err(XdgParseError::InvalidEntryType(other.to_string())),
+            None => Err(XdgParseError::MissingKey("Desktop Entry/Type".to_string())),
+        }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<XdgDirectoryEntry> {
+        XdgDesktopFile::from_path(path).and_then(XdgDirectoryEntry::new)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.file.get_str("Desktop Entry", "Name")
+    }
+
+    pub fn icon(&self) -> Option<&str> {
+        self.file.get_str("Desktop Entry", "Icon")
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.file.get_str("Desktop Entry", "Comment")
+    }
+
+    pub fn into_inner(self) -> XdgDesktopFile {
+        self.file
+    }
+}
+
+impl Deref for XdgDirectoryEntry {
+    type Target = XdgDesktopFile;
+
+    fn deref(&self) -> &XdgDesktopFile {
+        &self.file
+    }
+}
+
+impl FromStr for XdgDirectoryEntry {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<XdgDirectoryEntry> {
+        XdgDirectoryEntry::new(s.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_entry() {
+        let dir = XdgDirectoryEntry::from_str(
+            "[Desktop Entry]\nType=Directory\nName=Games\nIcon=applications-games\n",
+        )
+        .unwrap();
+        assert_eq!(dir.name(), Some("Games"));
+        assert_eq!(dir.icon(), Some("applications-games"));
+        assert_eq!(dir.comment(), None);
+        assert!(XdgDirectoryEntry::from_str("[Desktop Entry]\nType=Application\n").is_err());
+        assert!(XdgDirectoryEntry::from_str("[Desktop Entry]\nName=Games\n").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::xdg_desktop_file::XdgDesktopFile;
+use std::{convert::Infallible, fmt, str::FromStr};
+
+/// The value of the `Type` key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EntryType {
+    Application,
+    Link,
+    Directory,
+    /// A type not defined by the specification, which consumers should ignore.
+    Unknown(String),
+}
+
+impl EntryType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EntryType::Application => "Application",
+            EntryType::Link => "Link",
+            EntryType::Directory => "Directory",
+            EntryType::Unknown(s) => s,
+        }
+    }
+}
+
+impl From<&str> for EntryType {
+    fn from(s: &str) -> EntryType {
+        match s {
+            "Application" => EntryType::Application,
+            "Link" => EntryType::Link,
+            "Directory" => EntryType::Directory,
+            other => EntryType::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl FromStr for EntryType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<EntryType, Infallible> {
+        Ok(EntryType::from(s))
+    }
+}
+
+impl fmt::Display for EntryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl XdgDesktopFile {
+    /// The entry's `Type`, or `None` if the key is missing.
+    pub fn entry_type(&self) -> Option<EntryType> {
+        self.get_str("Desktop Entry", "Type").map(EntryType::from)
+    }
+
+    fn key_for_type(&self, entry_type: EntryType, key: &str) -> Option<&str> {
+        if self.entry_type()? != entry_type {
+            return None;
+        }
+        self.get_str("Desktop Entry", key)
+    }
+
+    /// The `Exec` command line of an `Application`. Returns `None` for other types, for
+    /// which the key has no meaning.
+    pub fn exec(&self) -> Option<&str> {
+        self.key_for_type(EntryType::Application, "Exec")
+    }
+
+    /// The `URL` of a `Link`. Returns `None` for other types, for which the key has no
+    /// meaning.
+    pub fn url(&self) -> Option<&str> {
+        self.key_for_type(EntryType::Link, "URL")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_accessors() {
+        let link = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.org\nExec=foo\n",
+        )
+        .unwrap();
+        assert_eq!(link.entry_type(), Some(EntryType::Link));
+        assert_eq!(link.url(), Some("https://example.org"));
+        assert_eq!(link.exec(), None);
+
+        let other = XdgDesktopFile::from_str("[Desktop Entry]\nType=Service\n").unwrap();
+        assert_eq!(
+            other.entry_type(),
+            Some(EntryType::Unknown("Service".into()))
+        );
+    }
+}
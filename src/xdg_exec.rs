@@ -0,0 +1,247 @@
+use crate::xdg_parse_error::XdgParseError;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    slice,
+};
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Resolves `program` the way a shell would: names containing a `/` are taken as paths, and
+/// anything else is searched for in `$PATH`. Returns the path of an executable file, if found.
+pub(crate) fn find_executable(program: &str) -> Option<PathBuf> {
+    if program.is_empty() {
+        return None;
+    }
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+/// An `Exec` value split into its program and arguments, with quoting and escaping removed.
+/// Field codes such as `%f` are kept until [`expand`](ExecCommand::expand) is called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecCommand {
+    args: Vec<String>,
+}
+
+/// The values substituted for an `Exec` command's field codes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FieldCodes<'a> {
+    /// The files or URLs to open, for `%f`, `%F`, `%u` and `%U`.
+    pub files: &'a [&'a str],
+    /// The entry's `Icon`, for `%i`.
+    pub icon: Option<&'a str>,
+    /// The entry's (translated) `Name`, for `%c`.
+    pub name: Option<&'a str>,
+    /// The location of the desktop file, for `%k`.
+    pub location: Option<&'a str>,
+}
+
+impl ExecCommand {
+    /// Splits an `Exec` value into arguments according to the specification's quoting
+    /// rules: arguments are separated by spaces, and may be enclosed in double quotes, inside
+    /// which `"`, `` ` ``, `$` and `\` must be escaped with a backslash. `exec` is the value
+    /// as [`XdgDesktopFile`](crate::XdgDesktopFile) holds it, with the general string escape
+    /// sequences already expanded, so that a literal backslash inside quotes is written `\\\\`
+    /// in the file.
+    pub fn parse(exec: &str) -> crate::Result<ExecCommand> {
+        let mut args = Vec::new();
+        let mut current = String::new();
+        // Distinguishes an empty quoted argument from no argument at all.
+        let mut has_arg = false;
+        let mut in_quotes = false;
+        let mut chars = exec.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                ' ' if !in_quotes => {
+                    if has_arg {
+                        args.push(std::mem::take(&mut current));
+                        has_arg = false;
+                    }
+                }
+                '"' => {
+                    in_quotes = !in_quotes;
+                    has_arg = true;
+                }
+                '\\' if in_quotes => match chars.next() {
+                    Some(e @ ('"' | '`' | '$' | '\\')) => current.push(e),
+                    _ => return Err(XdgParseError::InvalidExec(exec.to_string())),
+                },
+                c => {
+                    current.push(c);
+                    has_arg = true;
+                }
+            }
+        }
+        if in_quotes {
+            return Err(XdgParseError::InvalidExec(exec.to_string()));
+        }
+        if has_arg {
+            args.push(current);
+        }
+        if args.is_empty() {
+            return Err(XdgParseError::InvalidExec(exec.to_string()));
+        }
+        Ok(ExecCommand { args })
+    }
+
+    /// The program to run, as written; it may need resolving against `$PATH`.
+    pub fn program(&self) -> &str {
+        &self.args[0]
+    }
+
+    /// The program followed by its arguments, with field codes unexpanded.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Whether the command takes a single file or URL, with `%f` or `%u`.
+    pub fn takes_single_file(&self) -> bool {
+        self.args.iter().any(|arg| {
+            let mut chars = arg.chars();
+            // The character after a `%` is consumed with it, so `%%f` isn't a code.
+            while let Some(c) = chars.next() {
+                if c == '%' && matches!(chars.next(), Some('f' | 'u')) {
+                    return true;
+                }
+            }
+            false
+        })
+    }
+
+    /// Substitutes the field codes in the command's arguments. An argument that consists of
+    /// just `%F`, `%U` or `%i` may expand to several arguments, or to none. Codes that take a
+    /// single file only use the first of `codes.files`; see
+    /// [`expand_each`](ExecCommand::expand_each). Deprecated and unknown codes are removed.
+    pub fn expand(&self, codes: &FieldCodes<'_>) -> Vec<String> {
+        let mut out = Vec::with_capacity(self.args.len());
+        for arg in &self.args {
+            match arg.as_str() {
+                "%F" | "%U" => out.extend(codes.files.iter().map(|f| f.to_string())),
+                "%f" | "%u" => out.extend(codes.files.first().map(|f| f.to_string())),
+                "%i" => {
+                    if let Some(icon) = codes.icon {
+                        out.push("--icon".to_string());
+                        out.push(icon.to_string());
+                    }
+                }
+                _ => out.push(expand_embedded(arg, codes)),
+            }
+        }
+        out
+    }
+
+    /// Expands the command as [`expand`](ExecCommand::expand) does, once per instance of the
+    /// application to start: once per file if it
+    /// [takes a single file](ExecCommand::takes_single_file) and several are given, and once
+    /// otherwise.
+    pub fn expand_each(&self, codes: &FieldCodes<'_>) -> Vec<Vec<String>> {
+        if codes.files.len() <= 1 || !self.takes_single_file() {
+            return vec![self.expand(codes)];
+        }
+        codes
+            .files
+            .iter()
+            .map(|f| {
+                self.expand(&FieldCodes {
+                    files: slice::from_ref(f),
+                    ..*codes
+                })
+            })
+            .collect()
+    }
+}
+
+/// Substitutes the field codes within a single argument.
+fn expand_embedded(arg: &str, codes: &FieldCodes<'_>) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let value = match chars.next() {
+            Some('%') => Some("%"),
+            Some('f' | 'F' | 'u' | 'U') => codes.files.first().copied(),
+            Some('i') => codes.icon,
+            Some('c') => codes.name,
+            Some('k') => codes.location,
+            _ => None,
+        };
+        out.push_str(value.unwrap_or_default());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exec() {
+        let cmd = ExecCommand::parse(r#"foo --title "A \"quoted\" title" %U"#).unwrap();
+        assert_eq!(cmd.args(), ["foo", "--title", "A \"quoted\" title", "%U"]);
+        assert_eq!(
+            ExecCommand::parse(r#"a "" b"#).unwrap().args(),
+            ["a", "", "b"]
+        );
+        let escaped = ExecCommand::parse(r#""/opt/My\\ App/run" "#).unwrap();
+        assert_eq!(escaped.args(), [r"/opt/My\ App/run"]);
+        assert!(ExecCommand::parse(r#"foo "unterminated"#).is_err());
+        assert!(ExecCommand::parse("  ").is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let cmd = ExecCommand::parse("viewer %i --name=%c %F 100%%").unwrap();
+        let codes = FieldCodes {
+            files: &["a.png", "b.png"],
+            icon: Some("viewer"),
+            name: Some("Viewer"),
+            location: None,
+        };
+        assert_eq!(
+            cmd.expand(&codes),
+            [
+                "viewer",
+                "--icon",
+                "viewer",
+                "--name=Viewer",
+                "a.png",
+                "b.png",
+                "100%"
+            ]
+        );
+        assert_eq!(
+            cmd.expand(&FieldCodes::default()),
+            ["viewer", "--name=", "100%"]
+        );
+        assert!(!cmd.takes_single_file());
+        assert_eq!(cmd.expand_each(&codes).len(), 1);
+
+        let single = ExecCommand::parse("viewer --file=%f").unwrap();
+        assert!(single.takes_single_file());
+        assert_eq!(
+            single.expand_each(&codes),
+            [["viewer", "--file=a.png"], ["viewer", "--file=b.png"]]
+        );
+        assert!(!ExecCommand::parse("printf 100%%f")
+            .unwrap()
+            .takes_single_file());
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{
+    xdg_parse_error::XdgParseError,
+    xdg_section_name::{header_name, SectionName},
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, hash::Hash, iter, str::FromStr};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "std")]
+type IndexMap<K, V> = indexmap::IndexMap<K, V>;
+#[cfg(not(feature = "std"))]
+type IndexMap<K, V> = indexmap::IndexMap<K, V, core::hash::BuildHasherDefault<Fnv>>;
+
+/// The FNV-1a hash, standing in for `std`'s `RandomState`.
+#[cfg(not(feature = "std"))]
+pub(crate) struct Fnv(u64);
+
+#[cfg(not(feature = "std"))]
+impl Default for Fnv {
+    fn default() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::hash::Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A line of a file in the desktop entry syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Line<'a> {
+    Blank,
+    Comment,
+    /// A group header, holding the text between the brackets.
+    Header(&'a str),
+    Entry(&'a str, &'a str),
+    /// A line that is none of the above.
+    Malformed,
+}
+
+/// Classifies a line. Whitespace around the `=` of an entry is ignored, as the specification
+/// requires.
+pub(crate) fn classify(line: &str) -> Line<'_> {
+    if line.trim().is_empty() {
+        Line::Blank
+    } else if line.starts_with('#') {
+        Line::Comment
+    } else if let Some(name) = header_name(line) {
+        Line::Header(name)
+    } else if let Some((k, v)) = line.split_once('=') {
+        Line::Entry(k.trim_end(), v.trim_start())
+    } else {
+        Line::Malformed
+    }
+}
+
+/// Whether the character at byte `i` of `s` is escaped, i.e. preceded by an odd number of
+/// backslashes.
+fn is_escaped(s: &str, i: usize) -> bool {
+    s[..i].bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+/// Splits a list value at each `;` not escaped by a backslash. Per the specification, each
+/// element is terminated by a `;`, so a single trailing delimiter doesn't produce an empty
+/// final element; `a;;` is the two elements `a` and the empty string.
+pub(crate) fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(s);
+    iter::from_fn(move || {
+        let r = rest?;
+        let mut from = 0;
+        while let Some(i) = r[from..].find(';').map(|i| i + from) {
+            if is_escaped(r, i) {
+                from = i + 1;
+                continue;
+            }
+            rest = Some(&r[i + 1..]);
+            return Some(&r[..i]);
+        }
+        rest = None;
+        Some(r).filter(|r| !r.is_empty())
+    })
+}
+
+fn unescape_with(s: &str, list_item: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(';') if list_item => out.push(';'),
+            // Other sequences are left for the consumer, e.g. `Exec`'s quoting rules, to
+            // interpret.
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Expands the `\s`, `\n`, `\t`, `\r` and `\\` escape sequences of a string value.
+pub(crate) fn unescape(s: &str) -> String {
+    unescape_with(s, false)
+}
+
+/// Like [`unescape`], for an item of a list, which also expands `\;`.
+pub(crate) fn unescape_list_item(s: &str) -> String {
+    unescape_with(s, true)
+}
+
+/// Escapes backslashes and control characters for writing as a string value.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The groups of a file as its lines are read: the layer that [`KeyFile`],
+/// [`XdgDesktopFile`](crate::XdgDesktopFile) and
+/// [`XdgDesktopFileRef`](crate::XdgDesktopFileRef) all build on, so that they agree on which
+/// keys belong to which group. A group that appears more than once is reopened, so that the
+/// keys of each occurrence are merged into the first, as in GLib.
+pub(crate) struct Groups<N, G> {
+    groups: IndexMap<N, G>,
+    /// The index of the group that keys are being added to.
+    current: Option<usize>,
+}
+
+impl<N: Hash + Eq, G: Default> Groups<N, G> {
+    pub(crate) fn new() -> Groups<N, G> {
+        Groups {
+            groups: IndexMap::default(),
+            current: None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn contains(&self, name: &N) -> bool {
+        self.groups.contains_key(name)
+    }
+
+    /// Makes `name` the group that keys are added to, creating it if it hasn't appeared yet.
+    pub(crate) fn open(&mut self, name: N) {
+        let entry = self.groups.entry(name);
+        self.current = Some(entry.index());
+        entry.or_default();
+    }
+
+    /// The group that keys are added to, with its name. Fails for keys that come before the
+    /// first group header.
+    pub(crate) fn current(&mut self) -> crate::Result<(&N, &mut G)> {
+        let groups = &mut self.groups;
+        self.current
+            .and_then(move |i| groups.get_index_mut(i))
+            .ok_or(XdgParseError::Other(
+                "File contains keys without section header",
+            ))
+    }
+
+    pub(crate) fn into_map(self) -> IndexMap<N, G> {
+        self.groups
+    }
+}
+
+/// The variants of `locale` (in the `lang_COUNTRY.ENCODING@MODIFIER` form of `$LC_MESSAGES`)
+/// to look for in key suffixes, most specific first. The encoding is ignored.
+pub(crate) fn locale_variants(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split_once('.').map_or(rest, |(rest, _)| rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+    let mut variants = Vec::with_capacity(4);
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    if !lang.is_empty() {
+        variants.push(lang.to_string());
+    }
+    variants
+}
+
+/// A file in the desktop entry syntax (groups of `key=value` lines) with none of the desktop
+/// entry specification's semantics: values are strings, decoded only on request. This is the
+/// format shared by `mimeapps.list`, `index.theme`, D-Bus `.service` files and GLib's
+/// `GKeyFile`.
+///
+/// As in GLib, a group that appears more than once is merged into its first occurrence, and
+/// a repeated key overrides the earlier one. Lines that are neither comments, group headers
+/// nor key-value pairs are ignored. Comments aren't preserved.
+///
+/// [`XdgDesktopFile`](crate::XdgDesktopFile) reads groups and keys through the same layer,
+/// adding the specification's semantics on top: typed values, validation, and the
+/// [`ParserOptions`](crate::ParserOptions) policies. With the default options, the two agree
+/// on the groups and keys of any file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyFile {
+    groups: IndexMap<SectionName, IndexMap<String, String>>,
+}
+
+impl KeyFile {
+    pub fn new() -> KeyFile {
+        KeyFile::default()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<KeyFile> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Iterates over the groups in the order they first appear.
+    pub fn groups(&self) -> impl Iterator<Item = &SectionName> {
+        self.groups.keys()
+    }
+
+    pub fn has_group(&self, group: &str) -> bool {
+        self.groups.contains_key(group)
+    }
+
+    /// Iterates over the keys of `group` in order; empty if there is no such group.
+    pub fn keys(&self, group: &str) -> impl Iterator<Item = &str> {
+        self.groups
+            .get(group)
+            .into_iter()
+            .flat_map(IndexMap::keys)
+            .map(String::as_str)
+    }
+
+    /// The value of `key`, exactly as written.
+    pub fn raw_value(&self, group: &str, key: &str) -> Option<&str> {
+        self.groups.get(group)?.get(key).map(String::as_str)
+    }
+
+    /// The value of `key` with its escape sequences expanded.
+    pub fn string(&self, group: &str, key: &str) -> Option<String> {
+        self.raw_value(group, key).map(unescape)
+    }
+
+    /// The translation of `key` best matching `locale`, falling back to the untranslated
+    /// value; see [`XdgDesktopSection::get_localized`](crate::XdgDesktopSection::get_localized).
+    pub fn locale_string(&self, group: &str, key: &str, locale: &str) -> Option<String> {
+        locale_variants(locale)
+            .iter()
+            .find_map(|l| self.string(group, &format!("{}[{}]", key, l)))
+            .or_else(|| self.string(group, key))
+    }
+
+    /// The value of `key` split into a list at unescaped semicolons, with each item's escape
+    /// sequences (including `\;`) expanded.
+    pub fn string_list(&self, group: &str, key: &str) -> Option<Vec<String>> {
+        let raw = self.raw_value(group, key)?;
+        Some(split_list(raw).map(unescape_list_item).collect())
+    }
+
+    /// Sets `key` to `value` exactly as given, creating the group if needed. Fails if `group`
+    /// is not a valid group name.
+    pub fn set_raw(&mut self, group: &str, key: &str, value: &str) -> crate::Result<()> {
+        let group = SectionName::new(group)?;
+        self.groups
+            .entry(group)
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Sets `key` to `value`, escaping it as needed.
+    pub fn set_string(&mut self, group: &str, key: &str, value: &str) -> crate::Result<()> {
+        self.set_raw(group, key, &escape(value))
+    }
+
+    /// Sets `key` to a semicolon-terminated list of `items`, escaping each.
+    pub fn set_string_list<S: AsRef<str>>(
+        &mut self,
+        group: &str,
+        key: &str,
+        items: impl IntoIterator<Item = S>,
+    ) -> crate::Result<()> {
+        let mut value = String::new();
+        for item in items {
+            value.push_str(&escape(item.as_ref()).replace(';', "\\;"));
+            value.push(';');
+        }
+        self.set_raw(group, key, &value)
+    }
+
+    /// Removes `key` from `group`, returning its raw value if it was present.
+    pub fn remove(&mut self, group: &str, key: &str) -> Option<String> {
+        self.groups.get_mut(group)?.shift_remove(key)
+    }
+}
+
+impl FromStr for KeyFile {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<KeyFile> {
+        let mut groups: Groups<SectionName, IndexMap<String, String>> = Groups::new();
+        for ln in s.lines() {
+            match classify(ln) {
+                Line::Header(name) => groups.open(SectionName::new(name)?),
+                Line::Entry(k, v) => {
+                    groups.current()?.1.insert(k.to_string(), v.to_string());
+                }
+                Line::Blank | Line::Comment | Line::Malformed => {}
+            }
+        }
+        Ok(KeyFile {
+            groups: groups.into_map(),
+        })
+    }
+}
+
+impl fmt::Display for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, keys)) in self.groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", name)?;
+            for (k, v) in keys {
+                writeln!(f, "{}={}", k, v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_file() {
+        let input = "# D-Bus service\n[D-BUS Service]\nName=org.example.App\n\
+                     Exec=/usr/bin/app --gapplication-service\nNotAKeyLine\n\
+                     [Extra] \nGreeting = Hello\\sthere\nGreeting[de]=Hallo\n\
+                     Items=a\\;b;c;\n[D-BUS Service]\nUser=root\n";
+        let mut file = KeyFile::from_str(input).unwrap();
+        let groups: Vec<&str> = file.groups().map(SectionName::as_str).collect();
+        assert_eq!(groups, ["D-BUS Service", "Extra"]);
+        let keys: Vec<&str> = file.keys("D-BUS Service").collect();
+        assert_eq!(keys, ["Name", "Exec", "User"]);
+        assert_eq!(file.string("Extra", "Greeting").unwrap(), "Hello there");
+        assert_eq!(
+            file.locale_string("Extra", "Greeting", "de_CH").unwrap(),
+            "Hallo"
+        );
+        assert_eq!(file.string_list("Extra", "Items").unwrap(), ["a;b", "c"]);
+
+        file.set_string_list("Extra", "Items", ["x;y", "z\n"])
+            .unwrap();
+        assert_eq!(file.raw_value("Extra", "Items"), Some("x\\;y;z\\n;"));
+        assert_eq!(file.string_list("Extra", "Items").unwrap(), ["x;y", "z\n"]);
+        let reparsed = KeyFile::from_str(&file.to_string()).unwrap();
+        assert_eq!(reparsed, file);
+    }
+}
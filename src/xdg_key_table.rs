@@ -0,0 +1,153 @@
+use crate::{xdg_desktop_value::KeyType, xdg_standard_key::StandardKey};
+use std::collections::HashMap;
+
+/// The types of the keys of a dialect of the desktop entry format, which the parser uses to
+/// type their values. Keys are given without locale suffixes.
+///
+/// The [desktop entry specification's](KeyTable::desktop_entry) table is used by default;
+/// others can be added to it or used in its place with
+/// [`ParserOptions`](crate::ParserOptions).
+///
+/// ```
+/// # use xdg_desktop_parser::{KeyTable, KeyType, ParserOptions, XdgDesktopValue};
+/// let options = ParserOptions::new().with_key_table(&KeyTable::kde_service());
+/// let file = options
+///     .parse("[Desktop Entry]\nName=Thumbnailer\nInitialPreference=8\n")
+///     .unwrap();
+/// assert_eq!(
+///     file.get("Desktop Entry", "InitialPreference"),
+///     Some(&XdgDesktopValue::Numeric(8.0))
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyTable {
+    types: HashMap<String, KeyType>,
+}
+
+impl KeyTable {
+    /// An empty table.
+    pub fn new() -> KeyTable {
+        KeyTable::default()
+    }
+
+    /// The keys the desktop entry specification defines.
+    pub fn desktop_entry() -> KeyTable {
+        StandardKey::ALL
+            .iter()
+            .fold(KeyTable::new(), |t, k| t.with(k.as_str(), k.key_type()))
+    }
+
+    /// The keys KDE defines for service files: `.desktop` files that describe plugins, KIO
+    /// workers and other services rather than applications.
+    pub fn kde_service() -> KeyTable {
+        KeyTable::new()
+            .with("ServiceTypes", KeyType::Strings)
+            .with("X-KDE-ServiceTypes", KeyType::Strings)
+            .with("InitialPreference", KeyType::Numeric)
+            .with("X-KDE-Library", KeyType::String)
+            .with("X-KDE-Protocols", KeyType::Strings)
+            .with("X-KDE-FormFactors", KeyType::Strings)
+            .with("X-KDE-ParentApp", KeyType::String)
+            .with("X-KDE-StartupNotify", KeyType::Bool)
+            .with("X-KDE-SubstituteUID", KeyType::Bool)
+            .with("X-KDE-Username", KeyType::String)
+            .with("X-KDE-PluginInfo-Name", KeyType::String)
+            .with("X-KDE-PluginInfo-Author", KeyType::String)
+            .with("X-KDE-PluginInfo-Email", KeyType::String)
+            .with("X-KDE-PluginInfo-Version", KeyType::String)
+            .with("X-KDE-PluginInfo-Website", KeyType::String)
+            .with("X-KDE-PluginInfo-License", KeyType::String)
+            .with("X-KDE-PluginInfo-Category", KeyType::String)
+            .with("X-KDE-PluginInfo-Depends", KeyType::Strings)
+            .with("X-KDE-PluginInfo-EnabledByDefault", KeyType::Bool)
+            .with("X-DBUS-ServiceName", KeyType::String)
+            .with("X-DBUS-StartupType", KeyType::String)
+    }
+
+    /// Adds `key` to the table, replacing any type it already had.
+    pub fn with(mut self, key: &str, key_type: KeyType) -> KeyTable {
+        self.insert(key, key_type);
+        self
+    }
+
+    /// Adds `key` to the table, returning the type it had before, if any.
+    pub fn insert(&mut self, key: &str, key_type: KeyType) -> Option<KeyType> {
+        self.types.insert(key.to_string(), key_type)
+    }
+
+    pub fn get(&self, key: &str) -> Option<KeyType> {
+        self.types.get(key).copied()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.types.contains_key(key)
+    }
+
+    /// Iterates over the keys and their types, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, KeyType)> {
+        self.types.iter().map(|(k, t)| (k.as_str(), *t))
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{xdg_desktop_value::XdgDesktopValue, xdg_parser_options::ParserOptions};
+
+    #[test]
+    fn test_key_tables() {
+        let input = "[Desktop Entry]\nName=Plugin\nTerminal=false\nInitialPreference=2\n\
+                     X-KDE-ServiceTypes=KFileItemAction/Plugin;\nX-KDE-PluginInfo-Version=1.10\n";
+        let get = |options: &ParserOptions, key| {
+            options
+                .parse(input)
+                .unwrap()
+                .get("Desktop Entry", key)
+                .cloned()
+        };
+        let kde = ParserOptions::new().with_key_table(&KeyTable::kde_service());
+        assert_eq!(
+            get(&kde, "InitialPreference"),
+            Some(XdgDesktopValue::Numeric(2.0))
+        );
+        assert!(matches!(
+            get(&kde, "X-KDE-ServiceTypes"),
+            Some(XdgDesktopValue::List(l)) if l.len() == 1
+        ));
+        assert_eq!(
+            get(&kde, "X-KDE-PluginInfo-Version"),
+            Some(XdgDesktopValue::String("1.10".into()))
+        );
+        assert_eq!(get(&kde, "Terminal"), Some(XdgDesktopValue::Bool(false)));
+        let (_, report) = kde.parse_with_report(input).unwrap();
+        assert!(report.is_empty(), "{:?}", report);
+
+        // Extending the table never re-types the specification's keys, but replacing it does.
+        let extended =
+            ParserOptions::new().with_key_table(&KeyTable::new().with("Terminal", KeyType::String));
+        assert_eq!(
+            get(&extended, "Terminal"),
+            Some(XdgDesktopValue::Bool(false))
+        );
+        let replaced = ParserOptions::new()
+            .key_table(KeyTable::new().with("Terminal", KeyType::String))
+            .unknown_keys(crate::UnknownKeys::Raw);
+        assert_eq!(
+            get(&replaced, "Terminal"),
+            Some(XdgDesktopValue::String("false".into()))
+        );
+        assert_eq!(
+            get(&replaced, "InitialPreference"),
+            Some(XdgDesktopValue::String("2".into()))
+        );
+        assert_eq!(KeyTable::desktop_entry().len(), StandardKey::ALL.len());
+    }
+}
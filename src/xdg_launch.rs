@@ -0,0 +1,371 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile,
+    xdg_desktop_value::XdgDesktopValue,
+    xdg_exec::{ExecCommand, FieldCodes},
+    xdg_parse_error::XdgParseError,
+};
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{self, Child, Command},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Settings for launching applications from their desktop entries.
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    terminal: Vec<String>,
+    location: Option<PathBuf>,
+    pub(crate) launcher: String,
+    pub(crate) startup_id: Option<String>,
+    pub(crate) activation_token: Option<String>,
+    gpu_offload: bool,
+    nvidia_offload: bool,
+}
+
+/// A process started from a desktop entry.
+#[derive(Debug)]
+pub struct Launched {
+    pub child: Child,
+    /// The startup notification ID given to the process, if its entry has
+    /// `StartupNotify=true`. Window managers use it to match the new window to the launch.
+    pub startup_id: Option<String>,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> LaunchOptions {
+        LaunchOptions {
+            terminal: vec!["xterm".to_string(), "-e".to_string()],
+            location: None,
+            launcher: "xdg-desktop-parser".to_string(),
+            startup_id: None,
+            activation_token: None,
+            gpu_offload: false,
+            nvidia_offload: false,
+        }
+    }
+}
+
+impl LaunchOptions {
+    pub fn new() -> LaunchOptions {
+        LaunchOptions::default()
+    }
+
+    /// The command that entries with `Terminal=true` are run under; the application's
+    /// command line is appended to it. Defaults to `xterm -e`.
+    pub fn terminal<S: Into<String>>(mut self, command: impl IntoIterator<Item = S>) -> Self {
+        self.terminal = command.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The path of the desktop file, substituted for the `%k` field code.
+    pub fn location(mut self, path: impl Into<PathBuf>) -> Self {
+        self.location = Some(path.into());
+        self
+    }
+
+    /// The name of the launching program, used in generated startup notification IDs.
+    pub fn launcher(mut self, name: impl Into<String>) -> Self {
+        self.launcher = name.into();
+        self
+    }
+
+    /// The startup notification ID to hand to applications with `StartupNotify=true`,
+    /// typically one the caller has already announced to the window manager. One is
+    /// generated with [`new_startup_id`] if this isn't set.
+    pub fn startup_id(mut self, id: impl Into<String>) -> Self {
+        self.startup_id = Some(id.into());
+        self
+    }
+
+    /// The Wayland activation token, obtained from the compositor through the
+    /// `xdg_activation_v1` protocol, to export as `XDG_ACTIVATION_TOKEN`. Without one, the
+    /// startup notification ID is exported in its place.
+    pub fn activation_token(mut self, token: impl Into<String>) -> Self {
+        self.activation_token = Some(token.into());
+        self
+    }
+
+    /// Whether to honor `PrefersNonDefaultGPU` by exporting `DRI_PRIME=1`, which makes Mesa
+    /// render on the discrete GPU. Off by default, since the variable is harmful on systems
+    /// with a single GPU.
+    pub fn gpu_offload(mut self, enabled: bool) -> Self {
+        self.gpu_offload = enabled;
+        self
+    }
+
+    /// Whether the discrete GPU uses the proprietary NVIDIA driver, in which case
+    /// [`gpu_offload`](LaunchOptions::gpu_offload) also exports
+    /// `__NV_PRIME_RENDER_OFFLOAD=1` and `__GLX_VENDOR_LIBRARY_NAME=nvidia`. Off by default:
+    /// forcing the NVIDIA GLX library breaks GLX on systems without it, such as Intel and
+    /// AMD hybrids.
+    pub fn nvidia_offload(mut self, enabled: bool) -> Self {
+        self.nvidia_offload = enabled;
+        self
+    }
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .or_else(|| env::var("HOSTNAME").ok())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Generates a startup notification ID for launching `app` from `launcher`, unique to this
+/// host and process, in the `<launcher>-<pid>-<host>-<app>-<sequence>_TIME<timestamp>` form
+/// recommended by the Startup Notification specification. Without access to the X server's
+/// clock, the timestamp is taken from the system clock.
+pub fn new_startup_id(launcher: &str, app: &str) -> String {
+    static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u32);
+    let clean = |s: &str| s.replace(|c: char| c.is_whitespace() || c == '/', "_");
+    format!(
+        "{}-{}-{}-{}-{}_TIME{}",
+        clean(launcher),
+        process::id(),
+        clean(&hostname()),
+        clean(app),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        time
+    )
+}
+
+impl XdgDesktopFile {
+    /// Launches the application with `files` (paths or URLs, depending on the field codes
+    /// its `Exec` uses), using the default [`LaunchOptions`].
+    pub fn launch(&self, files: &[&str]) -> crate::Result<Child> {
+        self.launch_with(None, files, &LaunchOptions::default())
+    }
+
+    /// Launches the `[Desktop Action <action>]` named `action`, using the default
+    /// [`LaunchOptions`].
+    pub fn launch_action(&self, action: &str, files: &[&str]) -> crate::Result<Child> {
+        self.launch_with(Some(action), files, &LaunchOptions::default())
+    }
+
+    /// Launches the application, or one of its actions; see [`spawn`](XdgDesktopFile::spawn).
+    pub fn launch_with(
+        &self,
+        action: Option<&str>,
+        files: &[&str],
+        options: &LaunchOptions,
+    ) -> crate::Result<Child> {
+        Ok(self.spawn(action, files, options)?.child)
+    }
+
+    /// Launches the application, or one of its actions, by expanding the field codes of the
+    /// relevant `Exec` key. `Terminal=true` runs the command under `options`' terminal, `Path`
+    /// sets its working directory, and `PrefersNonDefaultGPU` selects the discrete GPU if
+    /// [`LaunchOptions::gpu_offload`] is enabled. With `StartupNotify=true`, the startup
+    /// notification ID is exported as `DESKTOP_STARTUP_ID` and `XDG_ACTIVATION_TOKEN` and
+    /// returned along with the process; otherwise both variables are cleared so the
+    /// application doesn't inherit the launcher's.
+    ///
+    /// Fails if `Exec` takes a single file, with `%f` or `%u`, and several `files` are given,
+    /// as the application must then be started once per file; see
+    /// [`spawn_each`](XdgDesktopFile::spawn_each).
+    pub fn spawn(
+        &self,
+        action: Option<&str>,
+        files: &[&str],
+        options: &LaunchOptions,
+    ) -> crate::Result<Launched> {
+        let mut commands = self.commands(action, files, options)?;
+        if commands.len() > 1 {
+            return Err(XdgParseError::Other(
+                "Exec takes a single file; use spawn_each to start one instance per file",
+            ));
+        }
+        self.spawn_command(commands.remove(0), options)
+    }
+
+    /// Launches the application, or one of its actions, as [`spawn`](XdgDesktopFile::spawn)
+    /// does, except that if `Exec` takes a single file and several `files` are given, one
+    /// instance is started per file, as the specification requires. A startup notification
+    /// ID or activation token set in `options` is only given to the first instance; the
+    /// others are given new IDs.
+    pub fn spawn_each(
+        &self,
+        action: Option<&str>,
+        files: &[&str],
+        options: &LaunchOptions,
+    ) -> crate::Result<Vec<Launched>> {
+        let commands = self.commands(action, files, options)?;
+        let mut rest = options.clone();
+        rest.startup_id = None;
+        rest.activation_token = None;
+        commands
+            .into_iter()
+            .enumerate()
+            .map(|(i, args)| self.spawn_command(args, if i == 0 { options } else { &rest }))
+            .collect()
+    }
+
+    /// The command lines to run to open `files`, one per instance of the application.
+    fn commands(
+        &self,
+        action: Option<&str>,
+        files: &[&str],
+        options: &LaunchOptions,
+    ) -> crate::Result<Vec<Vec<String>>> {
+        let section = match action {
+            Some(a) => format!("Desktop Action {}", a),
+            None => "Desktop Entry".to_string(),
+        };
+        let exec = self
+            .get_str(&section, "Exec")
+            .ok_or_else(|| XdgParseError::MissingKey(format!("{}/Exec", section)))?;
+        let location = options
+            .location
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .map(str::to_string);
+        let codes = FieldCodes {
+            files,
+            icon: self
+                .get_str(&section, "Icon")
+                .or_else(|| self.get_str("Desktop Entry", "Icon")),
+            name: self.get_str(&section, "Name"),
+            location: location.as_deref(),
+        };
+        let mut commands = ExecCommand::parse(exec)?.expand_each(&codes);
+        if let Some(XdgDesktopValue::Bool(true)) = self.get("Desktop Entry", "Terminal") {
+            for args in &mut commands {
+                args.splice(0..0, options.terminal.iter().cloned());
+            }
+        }
+        Ok(commands)
+    }
+
+    fn spawn_command(&self, args: Vec<String>, options: &LaunchOptions) -> crate::Result<Launched> {
+        let (program, rest) = match args.split_first() {
+            Some(split) => split,
+            None => return Err(XdgParseError::Other("Exec is empty")),
+        };
+        let mut command = Command::new(program);
+        command.args(rest);
+        if let Some(dir) = self.get_str("Desktop Entry", "Path") {
+            command.current_dir(dir);
+        }
+        if options.gpu_offload && self.launch_hints().prefers_non_default_gpu {
+            command.env("DRI_PRIME", "1");
+            if options.nvidia_offload {
+                command
+                    .env("__NV_PRIME_RENDER_OFFLOAD", "1")
+                    .env("__GLX_VENDOR_LIBRARY_NAME", "nvidia");
+            }
+        }
+        let startup_id = match self.get("Desktop Entry", "StartupNotify") {
+            Some(XdgDesktopValue::Bool(true)) => Some(
+                options
+                    .startup_id
+                    .clone()
+                    .unwrap_or_else(|| new_startup_id(&options.launcher, program)),
+            ),
+            _ => None,
+        };
+        match &startup_id {
+            Some(id) => {
+                let token = options.activation_token.as_ref().unwrap_or(id);
+                command
+                    .env("DESKTOP_STARTUP_ID", id)
+                    .env("XDG_ACTIVATION_TOKEN", token);
+            }
+            None => {
+                command
+                    .env_remove("DESKTOP_STARTUP_ID")
+                    .env_remove("XDG_ACTIVATION_TOKEN");
+            }
+        }
+        Ok(Launched {
+            child: command.spawn()?,
+            startup_id,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_launch() {
+        let entry = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nExec=sh -c \"test \\\\$PWD = / && exit 3\"\nPath=/\n\
+             \n[Desktop Action fail]\nExec=sh -c \"exit 4\"\n",
+        )
+        .unwrap();
+        let status = entry.launch(&[]).unwrap().wait().unwrap();
+        assert_eq!(status.code(), Some(3));
+        let status = entry.launch_action("fail", &[]).unwrap().wait().unwrap();
+        assert_eq!(status.code(), Some(4));
+        assert!(entry.launch_action("missing", &[]).is_err());
+
+        let single =
+            XdgDesktopFile::from_str("[Desktop Entry]\nExec=sh -c \"exit \\\\$1\" sh %f\n")
+                .unwrap();
+        assert!(single.launch(&["6", "7"]).is_err());
+        let codes: Vec<_> = single
+            .spawn_each(None, &["6", "7"], &LaunchOptions::new())
+            .unwrap()
+            .into_iter()
+            .map(|mut l| l.child.wait().unwrap().code())
+            .collect();
+        assert_eq!(codes, [Some(6), Some(7)]);
+
+        let terminal =
+            XdgDesktopFile::from_str("[Desktop Entry]\nExec=true\nTerminal=true\n").unwrap();
+        // The entry's command line becomes the script's positional parameters.
+        let options = LaunchOptions::new().terminal(["sh", "-c", "exit 5"]);
+        let status = terminal
+            .launch_with(None, &[], &options)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(status.code(), Some(5));
+    }
+
+    #[test]
+    fn test_startup_notification() {
+        let entry = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nExec=sh -c \"test \\\\$DESKTOP_STARTUP_ID = given\"\n\
+             StartupNotify=true\n",
+        )
+        .unwrap();
+        let options = LaunchOptions::new().startup_id("given");
+        let mut launched = entry.spawn(None, &[], &options).unwrap();
+        assert_eq!(launched.startup_id.as_deref(), Some("given"));
+        assert!(launched.child.wait().unwrap().success());
+
+        let mut generated = entry.spawn(None, &[], &LaunchOptions::new()).unwrap();
+        generated.child.wait().unwrap();
+        let id = generated.startup_id.unwrap();
+        assert!(id.starts_with("xdg-desktop-parser-"), "{}", id);
+        assert!(id.contains("-sh-") && id.contains("_TIME"), "{}", id);
+    }
+
+    #[test]
+    fn test_gpu_offload() {
+        let entry = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nExec=sh -c \"test \\\\$DRI_PRIME = 1 && \
+             test \\\\\"\\\\$__GLX_VENDOR_LIBRARY_NAME\\\\\" = \\\\\"\\\\$1\\\\\"\" sh %f\n\
+             PrefersNonDefaultGPU=true\n",
+        )
+        .unwrap();
+        let status = |options: &LaunchOptions, vendor: &str| {
+            let mut child = entry.launch_with(None, &[vendor], options).unwrap();
+            child.wait().unwrap().success()
+        };
+        let mesa = LaunchOptions::new().gpu_offload(true);
+        assert!(status(&mesa, ""));
+        assert!(!status(&mesa, "nvidia"));
+        assert!(status(&mesa.nvidia_offload(true), "nvidia"));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::xdg_desktop_file::XdgDesktopFile;
+
+/// Hints about how an application would like to be launched, from its `[Desktop Entry]`.
+/// Missing keys are treated as `false`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchHints {
+    /// `PrefersNonDefaultGPU`: the application should be run on a discrete GPU if one is
+    /// available.
+    pub prefers_non_default_gpu: bool,
+    /// `SingleMainWindow`: the application has a single main window, so launchers shouldn't
+    /// offer to open a new one.
+    pub single_main_window: bool,
+    /// `X-KDE-SubstituteUID`: the application should be run as another user.
+    pub substitute_uid: bool,
+    /// `X-KDE-Username`: the user to run the application as when `substitute_uid` is set,
+    /// `root` if not given.
+    pub username: Option<String>,
+}
+
+impl XdgDesktopFile {
+    /// The entry's [`LaunchHints`].
+    pub fn launch_hints(&self) -> LaunchHints {
+        let flag = |key| self.get_bool("Desktop Entry", key) == Some(true);
+        // Extension keys have no declared type, so their values are read as written.
+        let raw = |key| self.raw_value("Desktop Entry", key).map(str::trim);
+        LaunchHints {
+            prefers_non_default_gpu: flag("PrefersNonDefaultGPU"),
+            single_main_window: flag("SingleMainWindow"),
+            substitute_uid: raw("X-KDE-SubstituteUID") == Some("true"),
+            username: raw("X-KDE-Username").map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_launch_hints() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nPrefersNonDefaultGPU=true\nSingleMainWindow=true\n\
+             X-KDE-SubstituteUID=true\nX-KDE-Username=admin\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.launch_hints(),
+            LaunchHints {
+                prefers_non_default_gpu: true,
+                single_main_window: true,
+                substitute_uid: true,
+                username: Some("admin".to_string()),
+            }
+        );
+        let plain = XdgDesktopFile::from_str("[Desktop Entry]\nName=Foo\n").unwrap();
+        assert_eq!(plain.launch_hints(), LaunchHints::default());
+    }
+}
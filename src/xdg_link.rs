@@ -0,0 +1,39 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_parse_error::XdgParseError};
+use url::Url;
+
+impl XdgDesktopFile {
+    /// The target of a `Type=Link` entry, from its `URL` key. Returns `None` if the key is
+    /// missing, and an error if it isn't a valid absolute URL.
+    pub fn link_url(&self) -> Option<crate::Result<Url>> {
+        let url = self.get_str("Desktop Entry", "URL")?;
+        Some(Url::parse(url).map_err(|_| XdgParseError::InvalidUrl(url.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XdgParseWarning;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_link_url() {
+        let link = |url: &str| {
+            XdgDesktopFile::from_str(&format!(
+                "[Desktop Entry]\nType=Link\nName=Docs\nURL={}\n",
+                url
+            ))
+            .unwrap()
+        };
+        let docs = link("https://example.org/docs");
+        let url = docs.link_url().unwrap().unwrap();
+        assert_eq!(url.host_str(), Some("example.org"));
+        assert_eq!(docs.validate(), []);
+        let relative = link("docs/index.html");
+        assert!(relative.link_url().unwrap().is_err());
+        assert_eq!(
+            relative.validate(),
+            [XdgParseWarning::InvalidUrl("docs/index.html".into())]
+        );
+    }
+}
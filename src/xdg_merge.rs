@@ -0,0 +1,88 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::XdgDesktopValue};
+
+/// How [`XdgDesktopFile::merge_from_with`] combines a list value present in both files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMerge {
+    /// Use the overlay's list.
+    Replace,
+    /// Append the overlay's items that the base list doesn't already contain.
+    Append,
+}
+
+impl XdgDesktopFile {
+    /// Returns a copy of this file with every key of `overlay` applied on top, as when a
+    /// user's copy of an entry overrides selected keys of the system one. Keys and sections
+    /// `overlay` doesn't have are kept; overlay keys whose values failed to parse are
+    /// ignored. Lists are replaced; see [`merge_from_with`](XdgDesktopFile::merge_from_with).
+    pub fn merge_from(&self, overlay: &XdgDesktopFile) -> XdgDesktopFile {
+        self.merge_from_with(overlay, ListMerge::Replace)
+    }
+
+    /// Like [`merge_from`](XdgDesktopFile::merge_from), combining list values present in
+    /// both files according to `lists`.
+    pub fn merge_from_with(&self, overlay: &XdgDesktopFile, lists: ListMerge) -> XdgDesktopFile {
+        let mut out = self.clone();
+        for (name, section) in overlay.sections() {
+            for (key, value) in section {
+                let value = match (lists, self.get(name.as_str(), key), value) {
+                    (
+                        ListMerge::Append,
+                        Some(XdgDesktopValue::List(base)),
+                        XdgDesktopValue::List(items),
+                    ) => {
+                        let mut merged = base.clone();
+                        for item in items {
+                            let item_str = item.to_string();
+                            if !merged.iter().any(|m| m.to_string() == item_str) {
+                                merged.push(item.clone());
+                            }
+                        }
+                        XdgDesktopValue::List(merged)
+                    }
+                    _ => value.clone(),
+                };
+                // The name and key come from a parsed file and are written back as they were.
+                let _ = out.set_unchecked(name.as_str(), key, value);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_merge_from() {
+        let system = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nCategories=Utility;\n",
+        )
+        .unwrap();
+        let user = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nExec=foo --user\nCategories=Utility;Development;\nNoDisplay=true\n\
+             [Desktop Action New]\nName=New\n",
+        )
+        .unwrap();
+        let merged = system.merge_from(&user);
+        assert_eq!(merged.get_str("Desktop Entry", "Name"), Some("Foo"));
+        assert_eq!(merged.get_str("Desktop Entry", "Exec"), Some("foo --user"));
+        assert_eq!(merged.get_bool("Desktop Entry", "NoDisplay"), Some(true));
+        assert_eq!(merged.get_str("Desktop Action New", "Name"), Some("New"));
+        let categories = |f: &XdgDesktopFile| {
+            f.get_list("Desktop Entry", "Categories")
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(categories(&merged), ["Utility", "Development"]);
+
+        let overlay =
+            XdgDesktopFile::from_str("[Desktop Entry]\nCategories=Office;Utility;\n").unwrap();
+        let appended = system.merge_from_with(&overlay, ListMerge::Append);
+        assert_eq!(categories(&appended), ["Utility", "Office"]);
+        assert_eq!(categories(&system), ["Utility"]);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::XdgDesktopValue,
+    xdg_parse_error::XdgParseError,
+};
+use std::{fmt, str::FromStr};
+
+/// A MIME type such as `image/png`, as listed in an entry's `MimeType` key. The subtype may be
+/// the wildcard `*`, as in `image/*`, which matches every subtype.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MimeType {
+    media_type: String,
+    subtype: String,
+}
+
+/// Whether `s` is a valid type or subtype name, per RFC 6838: a letter or digit followed by
+/// letters, digits and `!#$&-^_.+`, up to 127 characters in all.
+fn is_restricted_name(s: &str) -> bool {
+    let b = s.as_bytes();
+    !b.is_empty()
+        && b.len() <= 127
+        && b[0].is_ascii_alphanumeric()
+        && b[1..]
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(c))
+}
+
+impl MimeType {
+    /// The top-level type, e.g. `image` in `image/png`.
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.subtype == "*"
+    }
+
+    /// Whether `self` and `other` name the same type, or one is a wildcard covering the other.
+    /// MIME types are compared case-insensitively.
+    pub fn matches(&self, other: &MimeType) -> bool {
+        self.media_type.eq_ignore_ascii_case(&other.media_type)
+            && (self.is_wildcard()
+                || other.is_wildcard()
+                || self.subtype.eq_ignore_ascii_case(&other.subtype))
+    }
+}
+
+impl FromStr for MimeType {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<MimeType> {
+        let (media_type, subtype) = s
+            .split_once('/')
+            .ok_or_else(|| XdgParseError::InvalidMimeType(s.to_string()))?;
+        if !is_restricted_name(media_type) || !(subtype == "*" || is_restricted_name(subtype)) {
+            return Err(XdgParseError::InvalidMimeType(s.to_string()));
+        }
+        Ok(MimeType {
+            media_type: media_type.to_string(),
+            subtype: subtype.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for MimeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.media_type, self.subtype)
+    }
+}
+
+impl XdgDesktopFile {
+    /// The entry's `MimeType`, in the order listed. Entries that aren't valid MIME types are
+    /// left out; [`validate`](XdgDesktopFile::validate) reports them.
+    pub fn mime_types(&self) -> Vec<MimeType> {
+        match self.get("Desktop Entry", "MimeType") {
+            Some(XdgDesktopValue::List(l)) => l
+                .iter()
+                .filter_map(XdgDesktopValue::string_value)
+                .filter_map(|m| m.parse().ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the entry declares support for `mime`, such as `image/png`. Wildcards are
+    /// honored on both sides: an entry listing `image/*` handles `image/png`, and `image/*`
+    /// matches an entry listing any image type. Returns `false` if `mime` isn't a valid MIME
+    /// type.
+    pub fn handles_mime(&self, mime: &str) -> bool {
+        match mime.parse::<MimeType>() {
+            Ok(mime) => self.mime_types().iter().any(|m| m.matches(&mime)),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handles_mime() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nMimeType=image/png;video/*;text/x-c++src;invalid;\n",
+        )
+        .unwrap();
+        let listed: Vec<String> = file.mime_types().iter().map(|m| m.to_string()).collect();
+        assert_eq!(listed, ["image/png", "video/*", "text/x-c++src"]);
+        assert!(file.handles_mime("image/png"));
+        assert!(file.handles_mime("IMAGE/PNG"));
+        assert!(file.handles_mime("image/*"));
+        assert!(file.handles_mime("video/mp4"));
+        assert!(file.handles_mime("text/x-c++src"));
+        assert!(!file.handles_mime("image/jpeg"));
+        assert!(!file.handles_mime("invalid"));
+        for invalid in &["image", "/png", "image/", "*/*", "image/p ng", "-image/png"] {
+            assert!(invalid.parse::<MimeType>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -1,12 +1,81 @@
-use std::{error::Error, fmt, num::ParseFloatError, str::ParseBoolError};
+use alloc::string::String;
+use core::{
+    error::Error,
+    fmt,
+    num::ParseFloatError,
+    str::{ParseBoolError, Utf8Error},
+};
+#[cfg(feature = "std")]
+use std::io;
 
 #[derive(Debug)]
 pub enum XdgParseError {
     ParseBoolError(ParseBoolError),
     ParseFloatError(ParseFloatError),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The input isn't valid UTF-8; the error gives the byte offset of the first invalid
+    /// sequence.
+    InvalidUtf8(Utf8Error),
+    InvalidSectionName(String),
+    /// A key given to a setter that isn't valid, such as one containing `=` or a newline.
+    InvalidKey(String),
+    MalformedLine(String),
+    DuplicateKey(String),
+    DuplicateSection(String),
+    UnknownSection(String),
+    MissingKey(String),
+    UnknownKey(String),
+    InvalidExec(String),
+    InvalidEntryType(String),
+    InvalidDate(String),
+    InvalidMenu(String),
+    InvalidVersion(String),
+    InvalidMimeType(String),
+    InvalidUrl(String),
+    /// A template placeholder for which no value was given.
+    UnknownPlaceholder(String),
+    /// A value converted to a Rust type that doesn't fit its variant, e.g. a list to `bool`.
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
     Other(&'static str),
 }
 
+/// `io::Error` can't be cloned, so a clone of an `Io` error keeps only its kind and message.
+impl Clone for XdgParseError {
+    fn clone(&self) -> Self {
+        match self {
+            XdgParseError::ParseBoolError(e) => XdgParseError::ParseBoolError(e.clone()),
+            XdgParseError::ParseFloatError(e) => XdgParseError::ParseFloatError(e.clone()),
+            #[cfg(feature = "std")]
+            XdgParseError::Io(e) => XdgParseError::Io(io::Error::new(e.kind(), e.to_string())),
+            XdgParseError::InvalidUtf8(e) => XdgParseError::InvalidUtf8(*e),
+            XdgParseError::InvalidSectionName(s) => XdgParseError::InvalidSectionName(s.clone()),
+            XdgParseError::InvalidKey(s) => XdgParseError::InvalidKey(s.clone()),
+            XdgParseError::MalformedLine(s) => XdgParseError::MalformedLine(s.clone()),
+            XdgParseError::DuplicateKey(s) => XdgParseError::DuplicateKey(s.clone()),
+            XdgParseError::DuplicateSection(s) => XdgParseError::DuplicateSection(s.clone()),
+            XdgParseError::UnknownSection(s) => XdgParseError::UnknownSection(s.clone()),
+            XdgParseError::MissingKey(s) => XdgParseError::MissingKey(s.clone()),
+            XdgParseError::UnknownKey(s) => XdgParseError::UnknownKey(s.clone()),
+            XdgParseError::InvalidExec(s) => XdgParseError::InvalidExec(s.clone()),
+            XdgParseError::InvalidEntryType(s) => XdgParseError::InvalidEntryType(s.clone()),
+            XdgParseError::InvalidDate(s) => XdgParseError::InvalidDate(s.clone()),
+            XdgParseError::InvalidMenu(s) => XdgParseError::InvalidMenu(s.clone()),
+            XdgParseError::InvalidVersion(s) => XdgParseError::InvalidVersion(s.clone()),
+            XdgParseError::InvalidMimeType(s) => XdgParseError::InvalidMimeType(s.clone()),
+            XdgParseError::InvalidUrl(s) => XdgParseError::InvalidUrl(s.clone()),
+            XdgParseError::UnknownPlaceholder(s) => XdgParseError::UnknownPlaceholder(s.clone()),
+            XdgParseError::TypeMismatch { expected, found } => {
+                XdgParseError::TypeMismatch { expected, found }
+            }
+            XdgParseError::Other(s) => XdgParseError::Other(s),
+        }
+    }
+}
+
 impl From<ParseBoolError> for XdgParseError {
     fn from(e: ParseBoolError) -> Self {
         XdgParseError::ParseBoolError(e)
@@ -19,6 +88,19 @@ impl From<ParseFloatError> for XdgParseError {
     }
 }
 
+#[cfg(feature = "std")]
+impl From<io::Error> for XdgParseError {
+    fn from(e: io::Error) -> Self {
+        XdgParseError::Io(e)
+    }
+}
+
+impl From<Utf8Error> for XdgParseError {
+    fn from(e: Utf8Error) -> Self {
+        XdgParseError::InvalidUtf8(e)
+    }
+}
+
 impl From<&'static str> for XdgParseError {
     fn from(e: &'static str) -> Self {
         XdgParseError::Other(e)
@@ -30,6 +112,28 @@ impl fmt::Display for XdgParseError {
         match self {
             XdgParseError::ParseBoolError(e) => e.fmt(f),
             XdgParseError::ParseFloatError(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            XdgParseError::Io(e) => e.fmt(f),
+            XdgParseError::InvalidUtf8(e) => e.fmt(f),
+            XdgParseError::InvalidSectionName(s) => write!(f, "Invalid section name: {}", s),
+            XdgParseError::InvalidKey(s) => write!(f, "Invalid key: {}", s),
+            XdgParseError::MalformedLine(s) => write!(f, "Malformed line: {}", s),
+            XdgParseError::DuplicateKey(s) => write!(f, "Duplicate key: {}", s),
+            XdgParseError::DuplicateSection(s) => write!(f, "Duplicate section: {}", s),
+            XdgParseError::UnknownSection(s) => write!(f, "Unknown section: {}", s),
+            XdgParseError::MissingKey(s) => write!(f, "Missing key: {}", s),
+            XdgParseError::UnknownKey(s) => write!(f, "Unknown key: {}", s),
+            XdgParseError::InvalidExec(s) => write!(f, "Invalid Exec value: {}", s),
+            XdgParseError::InvalidEntryType(s) => write!(f, "Unexpected entry type: {}", s),
+            XdgParseError::InvalidDate(s) => write!(f, "Invalid date: {}", s),
+            XdgParseError::InvalidMenu(s) => write!(f, "Invalid menu file: {}", s),
+            XdgParseError::InvalidVersion(s) => write!(f, "Invalid version: {}", s),
+            XdgParseError::InvalidMimeType(s) => write!(f, "Invalid MIME type: {}", s),
+            XdgParseError::InvalidUrl(s) => write!(f, "Invalid URL: {}", s),
+            XdgParseError::UnknownPlaceholder(s) => write!(f, "No value for placeholder: {}", s),
+            XdgParseError::TypeMismatch { expected, found } => {
+                write!(f, "Type mismatch: expected {}, found {}", expected, found)
+            }
             XdgParseError::Other(s) => write!(f, "{}", s),
         }
     }
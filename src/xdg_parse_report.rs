@@ -0,0 +1,207 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile,
+    xdg_desktop_value::{KeyType, XdgDesktopValue},
+    xdg_parse_warning::XdgParseWarning,
+    xdg_parser_options::ParserOptions,
+    xdg_section_name::SectionKind,
+};
+use std::slice;
+
+/// Keys that earlier versions of the specification defined and later ones dropped.
+const DEPRECATED_KEYS: &[&str] = &[
+    "Encoding",
+    "MiniIcon",
+    "TerminalOptions",
+    "Protocols",
+    "Extensions",
+    "BinaryPattern",
+    "MapNotify",
+    "SwallowTitle",
+    "SwallowExec",
+    "SortOrder",
+    "FilePattern",
+    "Dev",
+    "FSType",
+    "MountPoint",
+    "ReadOnly",
+    "UnmountIcon",
+];
+
+/// The non-fatal findings about a file: the [warnings](XdgDesktopFile::warnings) raised while
+/// parsing it, followed by those of a lint pass over its keys. None of them stop the file
+/// from being used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    warnings: Vec<XdgParseWarning>,
+}
+
+impl ParseReport {
+    pub fn warnings(&self) -> &[XdgParseWarning] {
+        &self.warnings
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, XdgParseWarning> {
+        self.warnings.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+impl IntoIterator for ParseReport {
+    type Item = XdgParseWarning;
+    type IntoIter = std::vec::IntoIter<XdgParseWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ParseReport {
+    type Item = &'a XdgParseWarning;
+    type IntoIter = slice::Iter<'a, XdgParseWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Why a value looks wrong, if it does.
+fn suspicious(key: &str, value: &XdgDesktopValue) -> Option<&'static str> {
+    let s = value.string_value()?;
+    match key {
+        "Name" | "Exec" | "Icon" | "URL" | "Type" if s.trim().is_empty() => Some("empty value"),
+        "Icon"
+            if !s.starts_with('/') && [".png", ".svg", ".xpm"].iter().any(|e| s.ends_with(e)) =>
+        {
+            Some("icon names should not include a file extension")
+        }
+        _ if s != s.trim_end() => Some("trailing whitespace"),
+        _ => None,
+    }
+}
+
+fn lint(file: &XdgDesktopFile, options: &ParserOptions, out: &mut Vec<XdgParseWarning>) {
+    for (name, section) in file.sections() {
+        // Extension groups may contain anything.
+        if !matches!(
+            name.kind(),
+            SectionKind::DesktopEntry | SectionKind::DesktopAction(_)
+        ) {
+            continue;
+        }
+        for (key, raw) in section.entries() {
+            let base = XdgDesktopValue::strip_locale(key);
+            let is_localized = base != key;
+            let key_type = options.key_type(&base);
+            let (section_name, key_name) = (name.to_string(), key.to_string());
+            if DEPRECATED_KEYS.contains(&base.as_str()) {
+                out.push(XdgParseWarning::DeprecatedKey {
+                    section: section_name.clone(),
+                    key: key_name.clone(),
+                });
+            } else if key_type.is_none() && !base.starts_with("X-") {
+                out.push(XdgParseWarning::UnknownKey {
+                    section: section_name.clone(),
+                    key: key_name.clone(),
+                });
+            }
+            let localizable = matches!(
+                key_type,
+                None | Some(KeyType::LocaleString)
+                    | Some(KeyType::LocaleStrings)
+                    | Some(KeyType::IconString)
+            );
+            if is_localized && !localizable {
+                out.push(XdgParseWarning::UnexpectedLocale {
+                    section: section_name.clone(),
+                    key: key_name.clone(),
+                });
+            }
+            if let Some(reason) = raw.ok().and_then(|v| suspicious(&base, v)) {
+                out.push(XdgParseWarning::SuspiciousValue {
+                    section: section_name,
+                    key: key_name,
+                    reason,
+                });
+            }
+        }
+    }
+}
+
+impl XdgDesktopFile {
+    /// Collects the file's parse warnings and the findings of a lint pass: unknown and
+    /// deprecated keys, locale suffixes on keys that can't be localized, and suspicious
+    /// values. Keys of `X-` extension groups are not checked.
+    pub fn report(&self) -> ParseReport {
+        self.report_with(&ParserOptions::default())
+    }
+
+    /// Like [`report`](XdgDesktopFile::report), treating keys declared in `options` as known.
+    pub(crate) fn report_with(&self, options: &ParserOptions) -> ParseReport {
+        let mut warnings = self.warnings().to_vec();
+        lint(self, options, &mut warnings);
+        ParseReport { warnings }
+    }
+}
+
+impl ParserOptions {
+    /// Parses a file and reports its non-fatal problems; see [`XdgDesktopFile::report`].
+    /// Keys declared with [`with_key_type`](ParserOptions::with_key_type) are not reported
+    /// as unknown.
+    pub fn parse_with_report(&self, s: &str) -> crate::Result<(XdgDesktopFile, ParseReport)> {
+        let file = self.parse(s)?;
+        let report = file.report_with(self);
+        Ok((file, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let input = "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nIcon=foo.png\n\
+                     Encoding=UTF-8\nTerminal[de]=false\nFrobnicate=1\nX-Foo-Bar=1\n\
+                     Name=Foo again\n[X-Vendor]\nAnything=1\n";
+        let (_, report) = XdgDesktopFile::parser()
+            .with_key_type("Frobnicate", KeyType::Numeric)
+            .parse_with_report(input)
+            .unwrap();
+        let section = || "Desktop Entry".to_string();
+        assert_eq!(
+            report.warnings(),
+            [
+                XdgParseWarning::DuplicateKey {
+                    section: section(),
+                    key: "Name".into(),
+                    line: 10
+                },
+                XdgParseWarning::SuspiciousValue {
+                    section: section(),
+                    key: "Icon".into(),
+                    reason: "icon names should not include a file extension"
+                },
+                XdgParseWarning::DeprecatedKey {
+                    section: section(),
+                    key: "Encoding".into()
+                },
+                XdgParseWarning::UnexpectedLocale {
+                    section: section(),
+                    key: "Terminal[de]".into()
+                },
+            ]
+        );
+        let file = XdgDesktopFile::parser().parse(input).unwrap();
+        assert!(file
+            .report()
+            .iter()
+            .any(|w| matches!(w, XdgParseWarning::UnknownKey { key, .. } if key == "Frobnicate")));
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+/// A problem found while parsing that, under the chosen
+/// [`ParserOptions`](crate::ParserOptions), didn't prevent the file from being parsed, or
+/// one found by [`XdgDesktopFile::validate`](crate::XdgDesktopFile::validate). Line numbers
+/// start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XdgParseWarning {
+    DuplicateKey {
+        section: String,
+        key: String,
+        line: usize,
+    },
+    DuplicateSection {
+        section: String,
+        line: usize,
+    },
+    MissingKey {
+        section: String,
+        key: String,
+    },
+    /// A section header whose name contains `[`, `]` or control characters, kept under
+    /// [`MalformedLines::Keep`](crate::MalformedLines::Keep). The section's keys are skipped.
+    InvalidSection {
+        section: String,
+        line: usize,
+    },
+    /// A line containing invalid UTF-8, which was replaced with U+FFFD under
+    /// [`ParserOptions::lossy_utf8`](crate::ParserOptions::lossy_utf8).
+    InvalidUtf8 {
+        line: usize,
+    },
+    /// A boolean written as `0`, `1` or with unusual capitalization, accepted under
+    /// [`ParserOptions::legacy_booleans`](crate::ParserOptions::legacy_booleans).
+    LegacyBoolean {
+        section: String,
+        key: String,
+        line: usize,
+    },
+    /// A key that the specification doesn't define for entries of this `Type`.
+    KeyNotApplicable {
+        key: String,
+        entry_type: String,
+    },
+    UnknownEntryType(String),
+    /// A key that is neither defined by the specification, declared with
+    /// [`ParserOptions::with_key_type`](crate::ParserOptions::with_key_type), nor an `X-`
+    /// extension key.
+    UnknownKey {
+        section: String,
+        key: String,
+    },
+    /// A key that earlier versions of the specification defined and later ones dropped.
+    DeprecatedKey {
+        section: String,
+        key: String,
+    },
+    /// A locale suffix on a key whose type can't be localized.
+    UnexpectedLocale {
+        section: String,
+        key: String,
+    },
+    /// A value that parsed but is probably not what its author intended.
+    SuspiciousValue {
+        section: String,
+        key: String,
+        reason: &'static str,
+    },
+    /// A `Version` that is malformed or isn't a published version of the specification.
+    UnknownVersion(String),
+    /// A key that was introduced in a later version of the specification than the entry's
+    /// `Version`.
+    KeyRequiresVersion {
+        key: String,
+        version: String,
+    },
+    /// `Categories` doesn't include any of the specification's main categories.
+    NoMainCategory,
+    /// An entry in `MimeType` that isn't of the form `type/subtype`.
+    InvalidMimeType(String),
+    /// The `URL` of a `Link` entry isn't a valid absolute URL. Only checked with the `url`
+    /// feature enabled.
+    InvalidUrl(String),
+    /// Both `OnlyShowIn` and `NotShowIn` are present, which the specification forbids.
+    ConflictingShowIn,
+    /// `Terminal` is `true`, but there is no `Exec` to run in the terminal.
+    TerminalWithoutExec,
+    /// `Hidden` is `true` in a file installed for all users. `Hidden` marks an entry as
+    /// deleted, which only makes sense in a user's copy overriding it.
+    HiddenSystemEntry,
+    /// `DBusActivatable` is `true`, but the desktop file ID, less `.desktop`, isn't a valid
+    /// D-Bus well-known name, so the application can't be activated.
+    InvalidDBusName(String),
+}
+
+impl fmt::Display for XdgParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XdgParseWarning::DuplicateKey { section, key, line } => write!(
+                f,
+                "Line {}: duplicate key {} in section {}",
+                line, key, section
+            ),
+            XdgParseWarning::DuplicateSection { section, line } => {
+                write!(f, "Line {}: duplicate section {}", line, section)
+            }
+            XdgParseWarning::MissingKey { section, key } => {
+                write!(f, "Missing required key {} in section {}", key, section)
+            }
+            XdgParseWarning::InvalidSection { section, line } => {
+                write!(f, "Line {}: invalid section name {}", line, section)
+            }
+            XdgParseWarning::InvalidUtf8 { line } => {
+                write!(f, "Line {}: invalid UTF-8 replaced", line)
+            }
+            XdgParseWarning::LegacyBoolean { section, key, line } => write!(
+                f,
+                "Line {}: deprecated boolean value for key {} in section {}",
+                line, key, section
+            ),
+            XdgParseWarning::KeyNotApplicable { key, entry_type } => {
+                write!(
+                    f,
+                    "Key {} is not valid for entries of type {}",
+                    key, entry_type
+                )
+            }
+            XdgParseWarning::UnknownEntryType(t) => write!(f, "Unknown entry type {}", t),
+            XdgParseWarning::UnknownKey { section, key } => {
+                write!(f, "Unknown key {} in section {}", key, section)
+            }
+            XdgParseWarning::DeprecatedKey { section, key } => {
+                write!(f, "Deprecated key {} in section {}", key, section)
+            }
+            XdgParseWarning::UnexpectedLocale { section, key } => write!(
+                f,
+                "Key {} in section {} has a locale but can't be localized",
+                key, section
+            ),
+            XdgParseWarning::SuspiciousValue {
+                section,
+                key,
+                reason,
+            } => write!(
+                f,
+                "Suspicious value for key {} in section {}: {}",
+                key, section, reason
+            ),
+            XdgParseWarning::UnknownVersion(v) => write!(f, "Unknown specification version {}", v),
+            XdgParseWarning::KeyRequiresVersion { key, version } => write!(
+                f,
+                "Key {} requires specification version {} or later",
+                key, version
+            ),
+            XdgParseWarning::NoMainCategory => f.write_str("Categories lists no main category"),
+            XdgParseWarning::InvalidMimeType(m) => write!(f, "Invalid MIME type {}", m),
+            XdgParseWarning::InvalidUrl(u) => write!(f, "Invalid URL {}", u),
+            XdgParseWarning::ConflictingShowIn => {
+                f.write_str("OnlyShowIn and NotShowIn are both present")
+            }
+            XdgParseWarning::TerminalWithoutExec => {
+                f.write_str("Terminal is true but there is no Exec")
+            }
+            XdgParseWarning::HiddenSystemEntry => {
+                f.write_str("Hidden is true in a system-wide entry")
+            }
+            XdgParseWarning::InvalidDBusName(n) => {
+                write!(f, "DBusActivatable is true but {} isn't a D-Bus name", n)
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::KeyType, xdg_key_table::KeyTable,
+};
+use std::{fmt, sync::Arc};
+
+/// How to handle lines that are neither comments, section headers nor key-value pairs, and
+/// section headers whose names are invalid. The keys under an invalid header are skipped
+/// unless the policy is `Error`; `Keep` reports the header in
+/// [`XdgDesktopFile::warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedLines {
+    /// Fail the whole parse.
+    Error,
+    /// Ignore the line.
+    Skip,
+    /// Store the line as a key whose value is an error.
+    Keep,
+}
+
+/// How to handle a key that appears more than once in a section, or a section that appears
+/// more than once in a file. The specification forbids both. Unless the policy is `Error`,
+/// each duplicate is reported in [`XdgDesktopFile::warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    Error,
+    FirstWins,
+    LastWins,
+}
+
+/// How to handle sections other than `[Desktop Entry]`, `[Desktop Action <name>]` and `X-`
+/// extension groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownSections {
+    Error,
+    /// Ignore the section and all of its keys.
+    Skip,
+    Keep,
+}
+
+/// A function that types the value of a key; see [`UnknownKeys::Custom`].
+pub type KeyTyper = Arc<dyn Fn(&str, &str) -> KeyType + Send + Sync>;
+
+/// How to type the values of keys the specification doesn't define and that have no type
+/// declared with [`ParserOptions::with_key_type`].
+#[derive(Clone)]
+pub enum UnknownKeys {
+    /// Keep every value as a string.
+    Raw,
+    /// Type each value as the most specific type it fits, so that `X-Version=1.10` becomes
+    /// the number `1.1`. Values containing an unescaped `;` become lists.
+    Guess,
+    /// Ask a function, given the key (less any locale suffix) and the value. The function
+    /// may capture state, such as a schema loaded at runtime.
+    Custom(KeyTyper),
+}
+
+impl UnknownKeys {
+    /// The `Custom` policy, calling `f`.
+    pub fn custom(f: impl Fn(&str, &str) -> KeyType + Send + Sync + 'static) -> UnknownKeys {
+        UnknownKeys::Custom(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for UnknownKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownKeys::Raw => f.write_str("Raw"),
+            UnknownKeys::Guess => f.write_str("Guess"),
+            UnknownKeys::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Options controlling how a file is parsed. The defaults are lenient; see
+/// [`strict`](ParserOptions::strict) for enforcing the specification.
+///
+/// ```
+/// # use xdg_desktop_parser::XdgDesktopFile;
+/// let file = XdgDesktopFile::parser()
+///     .strict(true)
+///     .parse("[Desktop Entry]\nName=Foo\n");
+/// assert!(file.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    key_table: KeyTable,
+    pub(crate) unknown_keys: UnknownKeys,
+    pub(crate) malformed_lines: MalformedLines,
+    pub(crate) duplicate_keys: Duplicates,
+    pub(crate) duplicate_sections: Duplicates,
+    pub(crate) unknown_sections: UnknownSections,
+    pub(crate) validate_locales: bool,
+    pub(crate) validate_keys: bool,
+    pub(crate) legacy_booleans: bool,
+    pub(crate) lossy_utf8: bool,
+    pub(crate) lossless: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        ParserOptions {
+            key_table: KeyTable::desktop_entry(),
+            unknown_keys: UnknownKeys::Guess,
+            malformed_lines: MalformedLines::Keep,
+            duplicate_keys: Duplicates::LastWins,
+            duplicate_sections: Duplicates::LastWins,
+            unknown_sections: UnknownSections::Keep,
+            validate_locales: false,
+            validate_keys: false,
+            legacy_booleans: true,
+            lossy_utf8: true,
+            lossless: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> ParserOptions {
+        ParserOptions::default()
+    }
+
+    /// Declares the type of a key that isn't defined by the specification, typically an `X-`
+    /// extension key. Without a declared type, such values are typed according to
+    /// [`unknown_keys`](ParserOptions::unknown_keys). Declarations for keys the specification
+    /// does define are ignored; see [`key_table`](ParserOptions::key_table) to re-type them.
+    pub fn with_key_type(mut self, key: &str, key_type: KeyType) -> ParserOptions {
+        if KeyType::of_standard_key(key).is_none() {
+            self.key_table.insert(key, key_type);
+        }
+        self
+    }
+
+    /// Declares the type of every key of `table`, as
+    /// [`with_key_type`](ParserOptions::with_key_type) does, e.g. to parse the keys of
+    /// [KDE service files](KeyTable::kde_service) as well as the specification's.
+    pub fn with_key_table(mut self, table: &KeyTable) -> ParserOptions {
+        for (key, key_type) in table.iter() {
+            self = self.with_key_type(key, key_type);
+        }
+        self
+    }
+
+    /// Types keys according to `table` alone, in place of the specification's
+    /// [table](KeyTable::desktop_entry), for dialects of the format that define the same keys
+    /// differently. `Version` is always a string, whatever the table says.
+    pub fn key_table(mut self, table: KeyTable) -> ParserOptions {
+        self.key_table = table;
+        self
+    }
+
+    /// The type values of `key` are parsed as, if the key table has one.
+    pub fn key_type(&self, key: &str) -> Option<KeyType> {
+        self.key_table.get(key)
+    }
+
+    /// How to type the values of unknown keys, [`UnknownKeys::Guess`] by default.
+    pub fn unknown_keys(mut self, policy: UnknownKeys) -> ParserOptions {
+        self.unknown_keys = policy;
+        self
+    }
+
+    /// Turns every policy to its strictest setting when `true`, or back to the lenient
+    /// defaults when `false`.
+    pub fn strict(mut self, strict: bool) -> ParserOptions {
+        let lenient = ParserOptions::default();
+        if strict {
+            self.malformed_lines = MalformedLines::Error;
+            self.duplicate_keys = Duplicates::Error;
+            self.duplicate_sections = Duplicates::Error;
+            self.unknown_sections = UnknownSections::Error;
+        } else {
+            self.malformed_lines = lenient.malformed_lines;
+            self.duplicate_keys = lenient.duplicate_keys;
+            self.duplicate_sections = lenient.duplicate_sections;
+            self.unknown_sections = lenient.unknown_sections;
+        }
+        self.validate_locales = strict;
+        self.validate_keys = strict;
+        self.legacy_booleans = !strict;
+        self.lossy_utf8 = !strict;
+        self
+    }
+
+    pub fn malformed_lines(mut self, policy: MalformedLines) -> ParserOptions {
+        self.malformed_lines = policy;
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: Duplicates) -> ParserOptions {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// With `LastWins`, a repeated section is merged into the earlier one, as
+    /// [`KeyFile`](crate::KeyFile) merges repeated groups; the keys it repeats are then
+    /// handled by the [`duplicate_keys`](ParserOptions::duplicate_keys) policy.
+    pub fn duplicate_sections(mut self, policy: Duplicates) -> ParserOptions {
+        self.duplicate_sections = policy;
+        self
+    }
+
+    pub fn unknown_sections(mut self, policy: UnknownSections) -> ParserOptions {
+        self.unknown_sections = policy;
+        self
+    }
+
+    /// Whether a key's locale suffix must be well-formed (`lang_COUNTRY@MODIFIER`, with the
+    /// country and modifier optional). Keys with an invalid suffix are treated as malformed
+    /// lines.
+    pub fn validate_locales(mut self, validate: bool) -> ParserOptions {
+        self.validate_locales = validate;
+        self
+    }
+
+    /// Whether keys may only contain the characters `A-Za-z0-9-`, besides their locale
+    /// suffix. Keys with other characters are treated as malformed lines.
+    pub fn validate_keys(mut self, validate: bool) -> ParserOptions {
+        self.validate_keys = validate;
+        self
+    }
+
+    /// Whether boolean keys also accept `0`, `1` and differently capitalized forms of `true`
+    /// and `false`, as found in older files. Each such value is reported in
+    /// [`XdgDesktopFile::warnings`]. Only `true` and `false` are valid otherwise.
+    pub fn legacy_booleans(mut self, accept: bool) -> ParserOptions {
+        self.legacy_booleans = accept;
+        self
+    }
+
+    /// Whether [`parse_bytes`](ParserOptions::parse_bytes) replaces invalid UTF-8 with U+FFFD,
+    /// reporting each affected line in [`XdgDesktopFile::warnings`], rather than failing.
+    pub fn lossy_utf8(mut self, lossy: bool) -> ParserOptions {
+        self.lossy_utf8 = lossy;
+        self
+    }
+
+    /// Whether to retain comments, blank lines and the original text of every line; see
+    /// [`XdgDesktopFile::from_str_lossless`].
+    pub fn lossless(mut self, lossless: bool) -> ParserOptions {
+        self.lossless = lossless;
+        self
+    }
+
+    pub fn parse(&self, s: &str) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::parse(s, self)
+    }
+
+    /// Parses a file that may not be valid UTF-8, such as one in a legacy encoding; see
+    /// [`lossy_utf8`](ParserOptions::lossy_utf8).
+    pub fn parse_bytes(&self, bytes: &[u8]) -> crate::Result<XdgDesktopFile> {
+        XdgDesktopFile::parse_bytes(bytes, self)
+    }
+}
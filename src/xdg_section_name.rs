@@ -0,0 +1,120 @@
+use crate::xdg_parse_error::XdgParseError;
+use alloc::string::{String, ToString};
+use core::{borrow::Borrow, fmt, str::FromStr};
+
+/// The name of a section (a "group" in the specification's terms), without the surrounding
+/// brackets of its header line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SectionName(String);
+
+/// The role of a section, as determined by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind<'a> {
+    /// `[Desktop Entry]`
+    DesktopEntry,
+    /// `[Desktop Action <name>]`, holding the action's name.
+    DesktopAction(&'a str),
+    /// A vendor extension group, e.g. `[X-KDE Extras]`.
+    Extension(&'a str),
+    /// Any other group, e.g. from a related specification that shares the file format.
+    Other(&'a str),
+}
+
+impl SectionName {
+    /// Validates a section name. Per the specification, names may not contain `[`, `]` or
+    /// control characters.
+    pub fn new(name: &str) -> crate::Result<SectionName> {
+        if !is_valid(name) {
+            return Err(XdgParseError::InvalidSectionName(name.to_string()));
+        }
+        Ok(SectionName(name.to_string()))
+    }
+
+    /// Parses a header line such as `[Desktop Entry]`.
+    pub fn from_header(line: &str) -> crate::Result<SectionName> {
+        match header_name(line) {
+            Some(name) => SectionName::new(name),
+            None => Err(XdgParseError::InvalidSectionName(line.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn kind(&self) -> SectionKind<'_> {
+        match self.0.as_str() {
+            "Desktop Entry" => SectionKind::DesktopEntry,
+            s if s.starts_with("Desktop Action ") => {
+                SectionKind::DesktopAction(&s["Desktop Action ".len()..])
+            }
+            s if s.starts_with("X-") => SectionKind::Extension(s),
+            s => SectionKind::Other(s),
+        }
+    }
+}
+
+pub(crate) fn is_valid(name: &str) -> bool {
+    !name.is_empty() && !name.chars().any(|c| c == '[' || c == ']' || c.is_control())
+}
+
+/// The text between the brackets of a header line. Trailing whitespace after the `]` is
+/// ignored.
+pub(crate) fn header_name(line: &str) -> Option<&str> {
+    line.trim_end().strip_prefix('[')?.strip_suffix(']')
+}
+
+impl FromStr for SectionName {
+    type Err = XdgParseError;
+
+    fn from_str(s: &str) -> crate::Result<SectionName> {
+        SectionName::new(s)
+    }
+}
+
+impl Borrow<str> for SectionName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SectionName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SectionName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_header() {
+        let name = SectionName::from_header("[Desktop Action New]").unwrap();
+        assert_eq!(name.as_str(), "Desktop Action New");
+        assert_eq!(name.kind(), SectionKind::DesktopAction("New"));
+        assert_eq!(
+            SectionName::from_header("[Desktop Entry]").unwrap().kind(),
+            SectionKind::DesktopEntry
+        );
+        assert_eq!(
+            SectionName::from_header("[X-Vendor]").unwrap().kind(),
+            SectionKind::Extension("X-Vendor")
+        );
+        assert_eq!(
+            SectionName::from_header("[Desktop Entry] \t")
+                .unwrap()
+                .kind(),
+            SectionKind::DesktopEntry
+        );
+        for invalid in &["[]", "[a]b]", "[a[b]", "[tab\t]", "Desktop Entry"] {
+            assert!(SectionName::from_header(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
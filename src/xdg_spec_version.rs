@@ -0,0 +1,100 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_parse_error::XdgParseError};
+use std::{fmt, str::FromStr};
+
+/// A version of the desktop entry specification, as given by the `Version` key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpecVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl SpecVersion {
+    pub const V1_0: SpecVersion = SpecVersion::new(1, 0);
+    pub const V1_1: SpecVersion = SpecVersion::new(1, 1);
+    pub const V1_2: SpecVersion = SpecVersion::new(1, 2);
+    pub const V1_3: SpecVersion = SpecVersion::new(1, 3);
+    pub const V1_4: SpecVersion = SpecVersion::new(1, 4);
+    pub const V1_5: SpecVersion = SpecVersion::new(1, 5);
+
+    /// The published versions of the specification, oldest first.
+    pub const KNOWN: &'static [SpecVersion] = &[
+        SpecVersion::V1_0,
+        SpecVersion::V1_1,
+        SpecVersion::V1_2,
+        SpecVersion::V1_3,
+        SpecVersion::V1_4,
+        SpecVersion::V1_5,
+    ];
+
+    pub const fn new(major: u32, minor: u32) -> SpecVersion {
+        SpecVersion { major, minor }
+    }
+
+    /// Whether this is a published version of the specification.
+    pub fn is_known(self) -> bool {
+        SpecVersion::KNOWN.contains(&self)
+    }
+
+    /// The version of the specification that introduced the standard key `key`, or `None`
+    /// for keys that date from 1.0 and keys the specification doesn't define.
+    pub fn introducing(key: &str) -> Option<SpecVersion> {
+        match key {
+            "Actions" | "DBusActivatable" | "Implements" => Some(SpecVersion::V1_1),
+            "PrefersNonDefaultGPU" => Some(SpecVersion::V1_4),
+            "SingleMainWindow" => Some(SpecVersion::V1_5),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = XdgParseError;
+
+    /// Parses a version of the form `major.minor`.
+    fn from_str(s: &str) -> crate::Result<SpecVersion> {
+        let invalid = || XdgParseError::InvalidVersion(s.to_string());
+        let (major, minor) = s.split_once('.').ok_or_else(invalid)?;
+        let number = |n: &str| {
+            if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            n.parse::<u32>().map_err(|_| invalid())
+        };
+        Ok(SpecVersion::new(number(major)?, number(minor)?))
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl XdgDesktopFile {
+    /// The version of the specification the entry conforms to, from its `Version` key.
+    /// Returns `None` if the key is missing, and an error if it isn't of the form
+    /// `major.minor`.
+    pub fn spec_version(&self) -> Option<crate::Result<SpecVersion>> {
+        self.get_str("Desktop Entry", "Version").map(str::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!("1.5".parse::<SpecVersion>().unwrap(), SpecVersion::V1_5);
+        assert_eq!(
+            "1.10".parse::<SpecVersion>().unwrap(),
+            SpecVersion::new(1, 10)
+        );
+        assert!(SpecVersion::new(1, 10) > SpecVersion::V1_5);
+        assert!(!SpecVersion::new(1, 10).is_known());
+        for invalid in &["1", "1.", ".5", "1.5.0", "1.+5", "v1.0", ""] {
+            assert!(invalid.parse::<SpecVersion>().is_err(), "{}", invalid);
+        }
+        assert_eq!(SpecVersion::V1_0.to_string(), "1.0");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{xdg_desktop_value::KeyType, xdg_parse_error::XdgParseError};
+use std::{convert::Infallible, fmt, str::FromStr};
+
+macro_rules! standard_keys {
+    ($($key:ident = $name:literal: $key_type:ident,)*) => {
+        /// A key defined by the specification for the `[Desktop Entry]` and
+        /// `[Desktop Action]` sections. Lookups such as [`XdgDesktopFile::get`] accept these
+        /// in place of key names.
+        ///
+        /// [`XdgDesktopFile::get`]: crate::XdgDesktopFile::get
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum StandardKey {
+            $($key,)*
+        }
+
+        impl StandardKey {
+            /// Every standard key, in the order the specification lists them.
+            pub const ALL: &'static [StandardKey] = &[$(StandardKey::$key,)*];
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(StandardKey::$key => $name,)*
+                }
+            }
+
+            /// The type of the key's value.
+            pub fn key_type(self) -> KeyType {
+                match self {
+                    $(StandardKey::$key => KeyType::$key_type,)*
+                }
+            }
+        }
+
+        impl FromStr for StandardKey {
+            type Err = XdgParseError;
+
+            /// Looks up a key by its exact name, without a locale suffix.
+            fn from_str(s: &str) -> crate::Result<StandardKey> {
+                match s {
+                    $($name => Ok(StandardKey::$key),)*
+                    other => Err(XdgParseError::UnknownKey(other.to_string())),
+                }
+            }
+        }
+    };
+}
+
+standard_keys! {
+    Type = "Type": String,
+    Version = "Version": String,
+    Name = "Name": LocaleString,
+    GenericName = "GenericName": LocaleString,
+    NoDisplay = "NoDisplay": Bool,
+    Comment = "Comment": LocaleString,
+    Icon = "Icon": IconString,
+    Hidden = "Hidden": Bool,
+    OnlyShowIn = "OnlyShowIn": Strings,
+    NotShowIn = "NotShowIn": Strings,
+    DBusActivatable = "DBusActivatable": Bool,
+    TryExec = "TryExec": String,
+    Exec = "Exec": String,
+    Path = "Path": String,
+    Terminal = "Terminal": Bool,
+    Actions = "Actions": Strings,
+    MimeType = "MimeType": Strings,
+    Categories = "Categories": Strings,
+    Implements = "Implements": Strings,
+    Keywords = "Keywords": LocaleStrings,
+    StartupNotify = "StartupNotify": Bool,
+    StartupWMClass = "StartupWMClass": String,
+    Url = "URL": String,
+    PrefersNonDefaultGPU = "PrefersNonDefaultGPU": Bool,
+    SingleMainWindow = "SingleMainWindow": Bool,
+}
+
+impl fmt::Display for StandardKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for StandardKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The name of a key, either one the specification defines or any other, such as an `X-`
+/// extension key or a localized key like `Name[de]`.
+///
+/// ```
+/// # use xdg_desktop_parser::{Key, StandardKey};
+/// assert_eq!(Key::from("Exec"), Key::Standard(StandardKey::Exec));
+/// assert_eq!(Key::from("X-Foo"), Key::Custom("X-Foo".to_string()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Standard(StandardKey),
+    Custom(String),
+}
+
+impl Key {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Key::Standard(k) => k.as_str(),
+            Key::Custom(s) => s,
+        }
+    }
+}
+
+impl From<StandardKey> for Key {
+    fn from(k: StandardKey) -> Key {
+        Key::Standard(k)
+    }
+}
+
+impl From<&str> for Key {
+    fn from(s: &str) -> Key {
+        s.parse()
+            .map(Key::Standard)
+            .unwrap_or_else(|_| Key::Custom(s.to_string()))
+    }
+}
+
+impl FromStr for Key {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Key, Infallible> {
+        Ok(Key::from(s))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for Key {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XdgDesktopFile;
+
+    #[test]
+    fn test_standard_keys() {
+        for key in StandardKey::ALL {
+            assert_eq!(key.as_str().parse::<StandardKey>().unwrap(), *key);
+        }
+        assert_eq!(StandardKey::Url.to_string(), "URL");
+        assert!("Name[de]".parse::<StandardKey>().is_err());
+
+        let file = XdgDesktopFile::from_str("[Desktop Entry]\nExec=foo\nX-Foo=bar\n").unwrap();
+        assert_eq!(
+            file.get_str("Desktop Entry", StandardKey::Exec),
+            Some("foo")
+        );
+        let custom = Key::from("X-Foo");
+        assert_eq!(file.raw_value("Desktop Entry", &custom), Some("bar"));
+    }
+}
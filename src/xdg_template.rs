@@ -0,0 +1,206 @@
+use crate::{
+    xdg_desktop_entry_builder::XdgDesktopEntryBuilder, xdg_desktop_file::XdgDesktopFile,
+    xdg_desktop_value::XdgDesktopValue, xdg_parse_error::XdgParseError,
+};
+use std::collections::HashMap;
+
+/// Characters that the specification reserves in `Exec` arguments, which must be quoted.
+const EXEC_RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+/// Quotes `s` for insertion into an `Exec` value, inside double quotes if `in_quotes` is set.
+fn quote_exec(s: &str, in_quotes: bool) -> String {
+    let s = s.replace('%', "%%");
+    if !in_quotes && !s.contains(EXEC_RESERVED) {
+        return s;
+    }
+    let mut out = String::with_capacity(s.len() + 2);
+    if !in_quotes {
+        out.push('"');
+    }
+    for c in s.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if !in_quotes {
+        out.push('"');
+    }
+    out
+}
+
+/// Replaces the `{name}` placeholders of `template`, a string value, with the values of
+/// `vars`, quoted if `exec` is set. Braces that don't enclose a name are left as they are.
+fn substitute(template: &str, vars: &HashMap<String, String>, exec: bool) -> crate::Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut in_quotes = false;
+    while let Some(open) = rest.find('{') {
+        let (before, after) = rest.split_at(open);
+        out.push_str(before);
+        if exec {
+            // A `"` preceded by a backslash is escaped, inside a quoted argument; any other
+            // opens or closes one.
+            let mut prev = None;
+            for c in before.chars() {
+                if c == '"' && prev != Some('\\') {
+                    in_quotes = !in_quotes;
+                }
+                prev = Some(c);
+            }
+        }
+        let name_len = after[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(after.len() - 1);
+        if name_len == 0 || !after[1 + name_len..].starts_with('}') {
+            out.push('{');
+            rest = &after[1..];
+            continue;
+        }
+        let name = &after[1..1 + name_len];
+        let value = vars
+            .get(name)
+            .ok_or_else(|| XdgParseError::UnknownPlaceholder(name.to_string()))?;
+        if exec {
+            out.push_str(&quote_exec(value, in_quotes));
+        } else {
+            out.push_str(value);
+        }
+        rest = &after[name_len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Substitutes the placeholders of a string value, or of each string in a list.
+fn render_value(
+    value: &XdgDesktopValue,
+    vars: &HashMap<String, String>,
+    exec: bool,
+) -> crate::Result<XdgDesktopValue> {
+    Ok(match value {
+        XdgDesktopValue::String(s) => XdgDesktopValue::String(substitute(s, vars, exec)?),
+        XdgDesktopValue::LocaleString(s) => {
+            XdgDesktopValue::LocaleString(substitute(s, vars, exec)?)
+        }
+        XdgDesktopValue::IconString(s) => XdgDesktopValue::IconString(substitute(s, vars, exec)?),
+        XdgDesktopValue::List(l) => XdgDesktopValue::List(
+            l.iter()
+                .map(|v| render_value(v, vars, exec))
+                .collect::<crate::Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+impl XdgDesktopFile {
+    /// Treats the file as a template, as packaging tools do to generate entries for many
+    /// applications: every `{name}` in a string value is replaced with the value of `name`
+    /// in `vars`, escaped so that it is read back exactly. In `Exec`, values are also quoted
+    /// as its rules require, and `%` is doubled so as not to start a field code. Fails if a
+    /// placeholder has no value. Braces that don't enclose a name of `A-Za-z0-9_-` are left
+    /// as they are.
+    ///
+    /// ```
+    /// # use xdg_desktop_parser::XdgDesktopFile;
+    /// let template: XdgDesktopFile = "[Desktop Entry]\nName={name}\nExec={prefix}/bin/app %U\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// let text = template
+    ///     .render([("name", "App"), ("prefix", "/opt/my apps")])
+    ///     .unwrap();
+    /// assert_eq!(text, "[Desktop Entry]\nName=App\nExec=\"/opt/my apps\"/bin/app %U\n");
+    /// ```
+    pub fn render<K, V>(&self, vars: impl IntoIterator<Item = (K, V)>) -> crate::Result<String>
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars: HashMap<String, String> = vars
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        let mut out = self.clone();
+        for (name, section) in self.sections() {
+            for (key, value) in section {
+                let rendered = render_value(value, &vars, key == "Exec")?;
+                if rendered != *value {
+                    out.set_unchecked(name.as_str(), key, rendered)?;
+                }
+            }
+        }
+        Ok(out.to_string())
+    }
+}
+
+impl XdgDesktopEntryBuilder {
+    /// Produces the entry's text with its placeholders substituted; see
+    /// [`XdgDesktopFile::render`].
+    pub fn render<K, V>(self, vars: impl IntoIterator<Item = (K, V)>) -> crate::Result<String>
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.build()?.render(vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{xdg_entry_type::EntryType, xdg_exec::ExecCommand};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_render() {
+        let vars = [
+            ("prefix", "/opt/My Apps"),
+            ("app", "tool"),
+            ("tag", "a;b"),
+            ("arg", "50% \"off\""),
+        ];
+        let text = XdgDesktopEntryBuilder::new(EntryType::Application)
+            .name("{app} {not a placeholder} {}")
+            .icon("{prefix}/share/{app}.png")
+            .exec("{prefix}/bin/{app} --label \"{arg}\" --raw {arg} %f")
+            .keywords(["{tag}", "{app}"])
+            .render(vars)
+            .unwrap();
+        let file = XdgDesktopFile::from_str(&text).unwrap();
+        assert_eq!(
+            file.get_str("Desktop Entry", "Name"),
+            Some("tool {not a placeholder} {}")
+        );
+        assert_eq!(
+            file.get_str("Desktop Entry", "Icon"),
+            Some("/opt/My Apps/share/tool.png")
+        );
+        let keywords: Vec<String> = file
+            .get_list("Desktop Entry", "Keywords")
+            .unwrap()
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        assert_eq!(keywords, ["a;b", "tool"]);
+        let exec = ExecCommand::parse(file.exec().unwrap()).unwrap();
+        assert_eq!(
+            exec.args(),
+            [
+                "/opt/My Apps/bin/tool",
+                "--label",
+                "50%% \"off\"",
+                "--raw",
+                "50%% \"off\"",
+                "%f"
+            ]
+        );
+
+        let unknown = XdgDesktopFile::from_str("[Desktop Entry]\nName={missing}\n")
+            .unwrap()
+            .render(vars);
+        assert!(matches!(unknown, Err(XdgParseError::UnknownPlaceholder(n)) if n == "missing"));
+    }
+}
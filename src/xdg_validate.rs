@@ -0,0 +1,288 @@
+use crate::{
+    xdg_category::Category, xdg_desktop_file::XdgDesktopFile, xdg_desktop_value::XdgDesktopValue,
+    xdg_entry_type::EntryType, xdg_mime_type::MimeType, xdg_parse_warning::XdgParseWarning,
+    xdg_spec_version::SpecVersion,
+};
+
+/// Keys only defined for entries of type `Application`.
+const APPLICATION_KEYS: &[&str] = &[
+    "Exec",
+    "TryExec",
+    "Path",
+    "Terminal",
+    "Actions",
+    "MimeType",
+    "Categories",
+    "Implements",
+    "Keywords",
+    "StartupNotify",
+    "StartupWMClass",
+    "PrefersNonDefaultGPU",
+    "SingleMainWindow",
+    "DBusActivatable",
+];
+
+impl XdgDesktopFile {
+    /// Checks the `[Desktop Entry]` section against the specification's requirements,
+    /// returning every violation found. An empty result means the entry is valid. Keys are
+    /// checked against the version of the specification given by `Version`, if any.
+    pub fn validate(&self) -> Vec<XdgParseWarning> {
+        let mut out = Vec::new();
+        let missing = |key: &str| XdgParseWarning::MissingKey {
+            section: "Desktop Entry".to_string(),
+            key: key.to_string(),
+        };
+        if self.get("Desktop Entry", "Name").is_none() {
+            out.push(missing("Name"));
+        }
+        let entry_type = match self.entry_type() {
+            Some(t) => t,
+            None => {
+                out.push(missing("Type"));
+                return out;
+            }
+        };
+        let has = |key: &str| self.get("Desktop Entry", key).is_some();
+        match &entry_type {
+            EntryType::Application => {
+                let dbus = matches!(
+                    self.get("Desktop Entry", "DBusActivatable"),
+                    Some(XdgDesktopValue::Bool(true))
+                );
+                if !has("Exec") && !dbus {
+                    out.push(missing("Exec"));
+                }
+            }
+            EntryType::Link if !has("URL") => out.push(missing("URL")),
+            #[cfg(feature = "url")]
+            EntryType::Link => {
+                if let Some(Err(_)) = self.link_url() {
+                    let url = self.get_str("Desktop Entry", "URL").unwrap_or_default();
+                    out.push(XdgParseWarning::InvalidUrl(url.to_string()));
+                }
+            }
+            EntryType::Unknown(t) => out.push(XdgParseWarning::UnknownEntryType(t.clone())),
+            _ => {}
+        }
+        if has("OnlyShowIn") && has("NotShowIn") {
+            out.push(XdgParseWarning::ConflictingShowIn);
+        }
+        if entry_type == EntryType::Application
+            && self.get_bool("Desktop Entry", "Terminal") == Some(true)
+            && !has("Exec")
+        {
+            out.push(XdgParseWarning::TerminalWithoutExec);
+        }
+        if has("Categories") && !self.categories().iter().any(Category::is_main) {
+            out.push(XdgParseWarning::NoMainCategory);
+        }
+        let mime_types = self
+            .get_list("Desktop Entry", "MimeType")
+            .unwrap_or_default();
+        for mime in mime_types.iter().filter_map(XdgDesktopValue::string_value) {
+            if !mime.is_empty() && mime.parse::<MimeType>().is_err() {
+                out.push(XdgParseWarning::InvalidMimeType(mime.to_string()));
+            }
+        }
+        // Types the specification doesn't define may give keys meanings of their own.
+        if matches!(entry_type, EntryType::Link | EntryType::Directory) {
+            for key in APPLICATION_KEYS.iter().filter(|k| has(k)) {
+                out.push(XdgParseWarning::KeyNotApplicable {
+                    key: key.to_string(),
+                    entry_type: entry_type.to_string(),
+                });
+            }
+        }
+        if matches!(entry_type, EntryType::Application | EntryType::Directory) && has("URL") {
+            out.push(XdgParseWarning::KeyNotApplicable {
+                key: "URL".to_string(),
+                entry_type: entry_type.to_string(),
+            });
+        }
+        match self.spec_version() {
+            Some(Ok(version)) if version.is_known() => {
+                let section = self
+                    .section("Desktop Entry")
+                    .into_iter()
+                    .flat_map(|s| s.keys());
+                for key in section {
+                    match SpecVersion::introducing(key) {
+                        Some(required) if required > version => {
+                            out.push(XdgParseWarning::KeyRequiresVersion {
+                                key: key.to_string(),
+                                version: required.to_string(),
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Newer versions may have changed the rules in ways this crate doesn't know.
+            Some(Ok(version)) => out.push(XdgParseWarning::UnknownVersion(version.to_string())),
+            Some(Err(_)) => {
+                let version = self.get_str("Desktop Entry", "Version").unwrap_or_default();
+                out.push(XdgParseWarning::UnknownVersion(version.to_string()));
+            }
+            None => {}
+        }
+        out
+    }
+
+    /// Like [`validate`](XdgDesktopFile::validate), also running the checks that depend on
+    /// how the file is installed: `id` is its desktop file ID, and `system_wide` whether it
+    /// is installed for all users, in `$XDG_DATA_DIRS` rather than `$XDG_DATA_HOME`.
+    pub fn validate_installed(&self, id: &str, system_wide: bool) -> Vec<XdgParseWarning> {
+        let mut out = self.validate();
+        let flag = |key| self.get_bool("Desktop Entry", key) == Some(true);
+        if system_wide && flag("Hidden") {
+            out.push(XdgParseWarning::HiddenSystemEntry);
+        }
+        let name = id.strip_suffix(".desktop").unwrap_or(id);
+        if flag("DBusActivatable") && !is_dbus_name(name) {
+            out.push(XdgParseWarning::InvalidDBusName(name.to_string()));
+        }
+        out
+    }
+}
+
+/// Whether `name` is a valid D-Bus well-known bus name: at most 255 characters, made of two
+/// or more `.`-separated elements of `A-Za-z0-9_-`, none starting with a digit.
+fn is_dbus_name(name: &str) -> bool {
+    name.len() <= 255
+        && name.split('.').count() >= 2
+        && name.split('.').all(|e| {
+            !e.is_empty()
+                && !e.starts_with(|c: char| c.is_ascii_digit())
+                && e.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::read_to_string, str::FromStr};
+
+    #[test]
+    fn test_validate() {
+        for f in &["test/Alacritty.desktop", "test/htop.desktop"] {
+            let file = XdgDesktopFile::from_str(&read_to_string(f).unwrap()).unwrap();
+            assert_eq!(file.validate(), [], "{}", f);
+        }
+        let link = XdgDesktopFile::from_str("[Desktop Entry]\nType=Link\nExec=foo\n").unwrap();
+        assert_eq!(
+            link.validate(),
+            [
+                XdgParseWarning::MissingKey {
+                    section: "Desktop Entry".into(),
+                    key: "Name".into()
+                },
+                XdgParseWarning::MissingKey {
+                    section: "Desktop Entry".into(),
+                    key: "URL".into()
+                },
+                XdgParseWarning::KeyNotApplicable {
+                    key: "Exec".into(),
+                    entry_type: "Link".into()
+                },
+            ]
+        );
+        let extension = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nType=X-Foo\nName=Foo\nExec=foo\nTerminal=true\nURL=foo:bar\n",
+        )
+        .unwrap();
+        assert_eq!(
+            extension.validate(),
+            [XdgParseWarning::UnknownEntryType("X-Foo".into())]
+        );
+    }
+
+    #[test]
+    fn test_main_category() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nCategories=TerminalEmulator;\n",
+        )
+        .unwrap();
+        assert_eq!(file.validate(), [XdgParseWarning::NoMainCategory]);
+    }
+
+    #[test]
+    fn test_invalid_mime_type() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nMimeType=text/plain;png;\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.validate(),
+            [XdgParseWarning::InvalidMimeType("png".into())]
+        );
+    }
+
+    #[test]
+    fn test_version_gating() {
+        let entry = |version: &str| {
+            XdgDesktopFile::from_str(&format!(
+                "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\n\
+                 PrefersNonDefaultGPU=true\nVersion={}\n",
+                version
+            ))
+            .unwrap()
+            .validate()
+        };
+        assert_eq!(entry("1.4"), []);
+        assert_eq!(
+            entry("1.1"),
+            [XdgParseWarning::KeyRequiresVersion {
+                key: "PrefersNonDefaultGPU".into(),
+                version: "1.4".into()
+            }]
+        );
+        assert_eq!(
+            entry("1.9"),
+            [XdgParseWarning::UnknownVersion("1.9".into())]
+        );
+        assert_eq!(
+            entry("one"),
+            [XdgParseWarning::UnknownVersion("one".into())]
+        );
+    }
+
+    #[test]
+    fn test_key_interactions() {
+        let entry = |keys: &str| {
+            XdgDesktopFile::from_str(&format!(
+                "[Desktop Entry]\nType=Application\nName=Foo\n{}",
+                keys
+            ))
+            .unwrap()
+        };
+        assert_eq!(
+            entry("Exec=foo\nOnlyShowIn=GNOME;\nNotShowIn=KDE;\n").validate(),
+            [XdgParseWarning::ConflictingShowIn]
+        );
+        assert_eq!(
+            entry("DBusActivatable=true\nTerminal=true\n").validate(),
+            [XdgParseWarning::TerminalWithoutExec]
+        );
+        let hidden = entry("Exec=foo\nHidden=true\n");
+        assert_eq!(hidden.validate_installed("foo.desktop", false), []);
+        assert_eq!(
+            hidden.validate_installed("foo.desktop", true),
+            [XdgParseWarning::HiddenSystemEntry]
+        );
+        let dbus = entry("Exec=foo\nDBusActivatable=true\n");
+        assert_eq!(dbus.validate_installed("org.example.Foo.desktop", true), []);
+        for invalid in &[
+            "foo.desktop",
+            "org.2example.Foo.desktop",
+            "org..Foo.desktop",
+        ] {
+            assert_eq!(
+                dbus.validate_installed(invalid, true),
+                [XdgParseWarning::InvalidDBusName(
+                    invalid.trim_end_matches(".desktop").to_string()
+                )]
+            );
+        }
+    }
+}
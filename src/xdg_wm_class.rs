@@ -0,0 +1,82 @@
+use crate::{xdg_desktop_file::XdgDesktopFile, xdg_exec::ExecCommand};
+use std::path::Path;
+
+/// What a window's `WM_CLASS` or Wayland app ID matched in an entry, from most to least
+/// reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WmClassMatch {
+    /// The entry's `StartupWMClass`.
+    StartupWMClass,
+    /// The entry's desktop file ID, less `.desktop`, as Wayland app IDs are meant to be.
+    DesktopFileId,
+    /// The file name of the program in the entry's `Exec`.
+    Exec,
+    /// The entry's untranslated `Name`.
+    Name,
+}
+
+impl XdgDesktopFile {
+    /// How well the entry with the desktop file ID `id`, if known, matches the window class
+    /// or app ID `wm_class`, if at all. Every comparison ignores ASCII case, since toolkits
+    /// disagree on how to capitalize the class.
+    pub fn wm_class_match(&self, id: Option<&str>, wm_class: &str) -> Option<WmClassMatch> {
+        if wm_class.is_empty() {
+            return None;
+        }
+        let is = |s: &str| s.eq_ignore_ascii_case(wm_class);
+        let get = |key| self.get_str("Desktop Entry", key);
+        if get("StartupWMClass").is_some_and(is) {
+            return Some(WmClassMatch::StartupWMClass);
+        }
+        if id.is_some_and(|id| is(id.strip_suffix(".desktop").unwrap_or(id))) {
+            return Some(WmClassMatch::DesktopFileId);
+        }
+        let program = get("Exec").and_then(|exec| ExecCommand::parse(exec).ok());
+        let program_name = program
+            .as_ref()
+            .and_then(|p| Path::new(p.program()).file_name()?.to_str());
+        if program_name.is_some_and(is) {
+            return Some(WmClassMatch::Exec);
+        }
+        get("Name").filter(|n| is(n)).map(|_| WmClassMatch::Name)
+    }
+
+    /// Whether a window with the class or app ID `wm_class` belongs to the entry, judging by
+    /// its `StartupWMClass`, `Exec` or `Name`; see
+    /// [`wm_class_match`](XdgDesktopFile::wm_class_match).
+    pub fn matches_wm_class(&self, wm_class: &str) -> bool {
+        self.wm_class_match(None, wm_class).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_wm_class_match() {
+        let file = XdgDesktopFile::from_str(
+            "[Desktop Entry]\nName=Alacritty\nExec=/usr/bin/alacritty -e sh\n\
+             StartupWMClass=Alacritty-Main\n",
+        )
+        .unwrap();
+        let id = Some("com.alacritty.Alacritty.desktop");
+        let rank = |wm_class| file.wm_class_match(id, wm_class);
+        assert_eq!(rank("alacritty-main"), Some(WmClassMatch::StartupWMClass));
+        assert_eq!(
+            rank("com.alacritty.Alacritty"),
+            Some(WmClassMatch::DesktopFileId)
+        );
+        assert_eq!(rank("alacritty"), Some(WmClassMatch::Exec));
+        assert_eq!(rank("kitty"), None);
+        assert_eq!(rank(""), None);
+        assert!(file.matches_wm_class("Alacritty"));
+        assert!(!file.matches_wm_class("com.alacritty.Alacritty"));
+        let named = XdgDesktopFile::from_str("[Desktop Entry]\nName=Foo Bar\nExec=foo\n").unwrap();
+        assert_eq!(
+            named.wm_class_match(None, "foo bar"),
+            Some(WmClassMatch::Name)
+        );
+    }
+}